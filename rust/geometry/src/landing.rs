@@ -0,0 +1,222 @@
+use crate::math;
+use crate::polygon::{next_point, prior_point};
+use crate::{AmbiguousNormal, LandingSurface, Vec2};
+
+// The parts of the player that matter when picking a surface to land on.
+#[derive(Debug, Clone, Copy)]
+pub struct Lander {
+    // Global position.
+    pub position: Vec2,
+    // Surfaces must be longer than this for the player to land on them.
+    pub min_surface_length: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum LandingError {
+    NoPlayerMotion,
+    // None of the candidate surfaces had enough room.
+    CouldNotLandAnywhere,
+    // A surface was found, but the direction of its normal is uncertain.
+    AmbiguousNormal(AmbiguousNormal),
+}
+
+// How the player can move from a surface they don't fit on, around one or more
+// corners, onto one that they do.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CornerShimmy {
+    // The corners to pass, in order.
+    pub corners: Vec<Vec2>,
+    // Where to end up. By convention, `a` is the last corner.
+    pub surface: LandingSurface,
+}
+
+// How many corners the player may shimmy around to find a surface.
+const MAX_SHIMMY_CORNERS: usize = 3;
+
+impl Lander {
+    // Return whether there is enough room for the player to land on the surface.
+    pub fn can_land_on_surface(&self, surface: &LandingSurface) -> bool {
+        surface.length_squared() > math::squared(self.min_surface_length)
+    }
+}
+
+pub fn pick_side_to_land_on(
+    lander: &Lander,
+    points: &[Vec2],
+    collision_position: Vec2,
+    player_motion: Vec2,
+    collision_normal: Vec2,
+) -> Result<Option<LandingSurface>, AmbiguousNormal> {
+    for i in 0..points.len() {
+        let i2 = next_point(points, i);
+        let a = points[i];
+        let b = points[i2];
+
+        // Construct a plane from a normal and a point. See
+        // https://docs.godotengine.org/en/stable/tutorials/math/vectors_advanced.html#constructing-a-plane-in-2d
+        let n_opt = math::normal(a, b, player_motion);
+        if n_opt.is_none() {
+            // This side is too small. Skip it.
+            continue;
+        }
+        let n = n_opt.unwrap();
+        let surface = LandingSurface { a, b, normal: n };
+        if surface.hit_by(collision_position, collision_normal) {
+            if !lander.can_land_on_surface(&surface) {
+                // A neighbor whose normal is ambiguous can't be landed on,
+                // unless neither neighbor is left.
+                let mut ambiguous = None;
+                let mut neighbor =
+                    |a: usize, b: usize| match LandingSurface::find_surface(points, a, b) {
+                        Ok(surface) => surface.filter(|s| lander.can_land_on_surface(s)),
+                        Err(error) => {
+                            ambiguous = Some(error);
+                            None
+                        }
+                    };
+                let next_surface = neighbor(i2, next_point(points, i2));
+                let prior_surface = neighbor(i, prior_point(points, i));
+                match (next_surface, prior_surface) {
+                    (None, None) => {
+                        if let Some(ambiguous) = ambiguous {
+                            return Err(ambiguous);
+                        }
+                    }
+                    (None, Some(prior_surface)) => return Ok(Some(prior_surface)),
+                    (Some(next_surface), None) => return Ok(Some(next_surface)),
+                    (Some(next_surface), Some(prior_surface)) => {
+                        // Pick the closer corner:
+                        let distance_squared =
+                            |s: &LandingSurface| lander.position.distance_squared_to(s.a);
+                        return if distance_squared(&next_surface) < distance_squared(&prior_surface)
+                        {
+                            Ok(Some(next_surface))
+                        } else {
+                            Ok(Some(prior_surface))
+                        };
+                    }
+                }
+            }
+            return Ok(Some(surface));
+        }
+    }
+    Ok(None)
+}
+
+pub fn pick_side_to_land_on_from_corner(
+    lander: &Lander,
+    points: &[Vec2],
+    index: usize,
+    player_motion: Vec2,
+    collision_normal: Vec2,
+) -> Result<LandingSurface, LandingError> {
+    if player_motion.is_zero_approx() {
+        // I hope to be able to avoid this by properly positioning the player such that new
+        // collisions are not generated.
+        return Err(LandingError::NoPlayerMotion);
+    }
+
+    let mut landing_surface_a = pick_adjacent_side(points, index, prior_point, player_motion);
+    let mut landing_surface_b = pick_adjacent_side(points, index, next_point, player_motion);
+    // First pick the surface whose normal is closest to the collision normal.
+    // Since we're dealing with normals, we can just use the one with the dot
+    // product that is larger.
+    if landing_surface_b.normal.dot(collision_normal)
+        > landing_surface_a.normal.dot(collision_normal)
+    {
+        std::mem::swap(&mut landing_surface_a, &mut landing_surface_b);
+    }
+
+    let mut error = LandingError::CouldNotLandAnywhere;
+    for surface in [&landing_surface_a, &landing_surface_b] {
+        if lander.can_land_on_surface(surface) {
+            // The surface picked the normal based on the player motion,
+            // which I would expect to generally work - the player must be
+            // colliding from outside the polygon. But corners are funny.
+            // We might pick a side such that the player's motion looks to
+            // come from inside.
+            match surface.correct_normal(points) {
+                Ok(surface) => return Ok(surface),
+                // Try the other side before giving up.
+                Err(ambiguous) => error = LandingError::AmbiguousNormal(ambiguous),
+            }
+        }
+    }
+    // TODO: We might hit this case if you land close to the branch - then we'll have to add
+    // in the adjacent tile.
+    Err(error)
+}
+
+fn pick_adjacent_side(
+    points: &[Vec2],
+    index: usize,
+    next_pt_fn: fn(&[Vec2], usize) -> usize,
+    player_motion: Vec2,
+) -> LandingSurface {
+    let next_point_index = next_pt_fn(points, index);
+    let a = points[index];
+    let b = points[next_point_index];
+    // `smooth_polygon` already checked the distance between these two points.
+    LandingSurface::new(a, b, player_motion).expect("surface should have a normal!")
+}
+
+// Given a `surface` of `points` that the player can't fit on, such as the tip
+// of a branch, find the closest surface further around the polygon in either
+// direction that they can.
+pub fn find_corner_shimmy(
+    lander: &Lander,
+    points: &[Vec2],
+    surface: &LandingSurface,
+) -> Result<Option<CornerShimmy>, AmbiguousNormal> {
+    let Some(i) = (0..points.len()).find(|i| {
+        let (a, b) = (points[*i], points[next_point(points, *i)]);
+        (a == surface.a && b == surface.b) || (a == surface.b && b == surface.a)
+    }) else {
+        return Ok(None);
+    };
+    let forward = shimmy_around_corners(lander, points, next_point(points, i), next_point)?;
+    let backward = shimmy_around_corners(lander, points, i, prior_point)?;
+    let path_length = |shimmy: &CornerShimmy| {
+        let mut from = lander.position;
+        let mut length = 0.0;
+        for corner in &shimmy.corners {
+            length += from.distance_to(*corner);
+            from = *corner;
+        }
+        length
+    };
+    Ok(match (forward, backward) {
+        (Some(forward), Some(backward)) => {
+            if path_length(&forward) <= path_length(&backward) {
+                Some(forward)
+            } else {
+                Some(backward)
+            }
+        }
+        (forward, backward) => forward.or(backward),
+    })
+}
+
+// Walk around `points` from the corner at `index`, using `next_pt_fn`, until
+// finding a surface the player fits on.
+fn shimmy_around_corners(
+    lander: &Lander,
+    points: &[Vec2],
+    index: usize,
+    next_pt_fn: fn(&[Vec2], usize) -> usize,
+) -> Result<Option<CornerShimmy>, AmbiguousNormal> {
+    let mut corners = Vec::new();
+    let mut index = index;
+    for _ in 0..MAX_SHIMMY_CORNERS {
+        corners.push(points[index]);
+        let next_index = next_pt_fn(points, index);
+        let Some(surface) = LandingSurface::find_surface(points, index, next_index)? else {
+            return Ok(None);
+        };
+        if lander.can_land_on_surface(&surface) {
+            return Ok(Some(CornerShimmy { corners, surface }));
+        }
+        index = next_index;
+    }
+    Ok(None)
+}
@@ -1,21 +1,30 @@
 use crate::math;
-use godot::classes::Geometry2D;
-use godot::global::absf;
-use godot::prelude::*;
+use crate::polygon;
+use crate::Vec2;
 
 // Global positions for two end points of a surface, along with the normal.
 // Note that in some cases, the end point is just the end of a tile, and the
 // surface may extend further.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct LandingSurface {
     // By convention, if the player landed on a corner, it should be `a`.
-    pub a: Vector2,
-    pub b: Vector2,
-    pub normal: Vector2,
+    pub a: Vec2,
+    pub b: Vec2,
+    pub normal: Vec2,
+}
+
+// Returned by `LandingSurface::correct_normal` when both sides of the surface
+// appear to be inside the polygon, so the direction of the normal could not be
+// determined.
+#[derive(Debug, Clone, Copy)]
+pub struct AmbiguousNormal {
+    // The surface, unchanged.
+    pub surface: LandingSurface,
+    pub test_points: [Vec2; 2],
 }
 
 impl LandingSurface {
-    pub fn new(a: Vector2, b: Vector2, player_motion: Vector2) -> Option<LandingSurface> {
+    pub fn new(a: Vec2, b: Vec2, player_motion: Vec2) -> Option<LandingSurface> {
         if let Some(normal) = math::normal(a, b, player_motion) {
             return Some(LandingSurface { a, b, normal });
         }
@@ -31,67 +40,62 @@ impl LandingSurface {
     // normal so that it points outside the polygon.
     // TODO: Update existing call sites to compute the normal in a similar
     // manner?
-    pub fn correct_normal(self, polygon: &PackedVector2Array) -> LandingSurface {
+    pub fn correct_normal(self, polygon: &[Vec2]) -> Result<LandingSurface, AmbiguousNormal> {
         // Pick a test point just off the middle of the line segment. Assuming
         // relatively simple shapes, this should determine the proper direction.
         let test_point = (self.a + self.b) / 2.0 + self.normal;
 
-        // Warning: there are known bugs in this method, e.g.
-        // https://github.com/godotengine/godot/issues/82305. So far it seems to
-        // work in my test cases.
-        if Geometry2D::singleton().is_point_in_polygon(test_point, polygon) {
+        if polygon::is_point_in_polygon(test_point, polygon) {
             // Check the other direction, too. If that point is *also* in the
             // polygon, we'll need to revisit this.
             let test_point2 = test_point + -2.0 * self.normal;
-            if Geometry2D::singleton().is_point_in_polygon(test_point2, polygon) {
-                godot_error!("Both test points are in polygon!");
-                godot_print!(
-                    "Both test points, {test_point} and {test_point2}, are in {polygon:?}"
-                );
-
-                // Both are no good, so use the original?
-                return self;
+            if polygon::is_point_in_polygon(test_point2, polygon) {
+                return Err(AmbiguousNormal {
+                    surface: self,
+                    test_points: [test_point, test_point2],
+                });
             }
-            return LandingSurface {
+            return Ok(LandingSurface {
                 a: self.a,
                 b: self.b,
                 normal: -self.normal,
-            };
+            });
         }
-        return self;
+        Ok(self)
     }
 
     // Given two indices on a polygon, which are either consecutive or can be
-    // treated as such, return a `LandingSurface` (if any).
+    // treated as such, return a `LandingSurface` (if any), or an error if the
+    // direction of its normal is ambiguous (see `correct_normal`).
     pub fn find_surface(
-        polygon: &PackedVector2Array,
+        polygon: &[Vec2],
         i: usize,
         i2: usize,
-    ) -> Option<LandingSurface> {
+    ) -> Result<Option<LandingSurface>, AmbiguousNormal> {
         let a = polygon[i];
         let b = polygon[i2];
-        let normal = (a - b).orthogonal().try_normalized()?;
+        let Some(normal) = (a - b).orthogonal().try_normalized() else {
+            return Ok(None);
+        };
         let surface = LandingSurface { a, b, normal };
-        Some(surface.correct_normal(polygon))
+        surface.correct_normal(polygon).map(Some)
     }
 
-    pub fn hit_by(&self, collision_position: Vector2, collision_normal: Vector2) -> bool {
+    pub fn hit_by(&self, collision_position: Vec2, collision_normal: Vec2) -> bool {
         // A plane is defined by a normal and a distance from the origin.
         let d = self.normal.dot(self.a);
 
         // Compute distance from collision to the plane.
         let distance = self.normal.dot(collision_position) - d;
-        const TOLERANCE: f64 = 0.2;
-        if absf(distance as f64) < TOLERANCE {
-            if crate::math::same_normals_approx(self.normal, collision_normal) {
-                // If the collision is between the two points, the length of ab
-                // should roughly equal ac + bc.
-                let ab = (self.a - self.b).length();
-                let ac = (self.a - collision_position).length();
-                let bc = (self.b - collision_position).length();
-                let diff = ab - (ac + bc);
-                return absf(diff as f64) < TOLERANCE;
-            }
+        const TOLERANCE: f32 = 0.2;
+        if distance.abs() < TOLERANCE && math::same_normals_approx(self.normal, collision_normal) {
+            // If the collision is between the two points, the length of ab
+            // should roughly equal ac + bc.
+            let ab = (self.a - self.b).length();
+            let ac = (self.a - collision_position).length();
+            let bc = (self.b - collision_position).length();
+            let diff = ab - (ac + bc);
+            return diff.abs() < TOLERANCE;
         }
         false
     }
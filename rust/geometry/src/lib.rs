@@ -0,0 +1,12 @@
+mod landing;
+mod landing_surface;
+pub mod math;
+pub mod polygon;
+mod vector;
+
+pub use landing::{
+    find_corner_shimmy, pick_side_to_land_on, pick_side_to_land_on_from_corner, CornerShimmy,
+    Lander, LandingError,
+};
+pub use landing_surface::{AmbiguousNormal, LandingSurface};
+pub use vector::Vec2;
@@ -0,0 +1,19 @@
+use crate::Vec2;
+
+// Assuming the player collided with the surface specified by points (a, b),
+// Return the normal vector pointing towards the player.
+pub fn normal(a: Vec2, b: Vec2, player_motion: Vec2) -> Option<Vec2> {
+    let ortho = (b - a).orthogonal();
+    (-player_motion).project(ortho).try_normalized()
+}
+
+// Succinct version.
+// TODO: Convert to an extension trait?
+pub fn squared(n: f32) -> f32 {
+    n * n
+}
+
+pub fn same_normals_approx(n1: Vec2, n2: Vec2) -> bool {
+    const TOLERANCE: f32 = 0.05;
+    n1.angle_to(n2).abs() < TOLERANCE
+}
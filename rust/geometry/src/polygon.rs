@@ -0,0 +1,230 @@
+use crate::math;
+use crate::{LandingSurface, Vec2};
+
+// Index of the point after `i`, wrapping around.
+pub fn next_point<T>(points: &[T], i: usize) -> usize {
+    assert!(i < points.len());
+    if i == points.len() - 1 {
+        0
+    } else {
+        i + 1
+    }
+}
+
+// Index of the point before `i`, wrapping around.
+pub fn prior_point<T>(points: &[T], i: usize) -> usize {
+    assert!(i < points.len());
+    if i == 0 {
+        points.len() - 1
+    } else {
+        i - 1
+    }
+}
+
+// Whether `point` is inside `polygon`, using the even-odd rule. Written to
+// avoid depending on `Geometry2D::is_point_in_polygon`, which has known bugs,
+// e.g. https://github.com/godotengine/godot/issues/82305.
+pub fn is_point_in_polygon(point: Vec2, polygon: &[Vec2]) -> bool {
+    if polygon.len() < 3 {
+        return false;
+    }
+    // Cast a ray in the +x direction and count the edges it crosses. Each edge
+    // includes its lower end point but not its upper one, so a ray through a
+    // vertex is only counted once.
+    let mut inside = false;
+    for i in 0..polygon.len() {
+        let a = polygon[i];
+        let b = polygon[next_point(polygon, i)];
+        if (a.y > point.y) != (b.y > point.y) {
+            let x_intersection = a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x);
+            if point.x < x_intersection {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+// Distance from `point` to the closest point on the segment from `a` to `b`.
+pub fn distance_to_segment(point: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    let length_squared = ab.length_squared();
+    if length_squared == 0.0 {
+        return point.distance_to(a);
+    }
+    let t = ((point - a).dot(ab) / length_squared).clamp(0.0, 1.0);
+    point.distance_to(a + ab * t)
+}
+
+// Distance from `point` to the closest edge of `polygon`, whether `point` is
+// inside or outside it.
+pub fn distance_to_edges(point: Vec2, polygon: &[Vec2]) -> f32 {
+    (0..polygon.len())
+        .map(|i| distance_to_segment(point, polygon[i], polygon[next_point(polygon, i)]))
+        .fold(f32::INFINITY, f32::min)
+}
+
+// Join `hole` to the `outer` polygon that contains it with a bridge between
+// their closest points, giving a single polygon whose inside (by the even-odd
+// rule) excludes the hole. The bridge is traversed in both directions, so both
+// of its sides are inside, and its normal is always ambiguous. Other holes in
+// `outer`, which the bridge may cross, are ignored.
+pub fn join_hole(outer: &[Vec2], hole: &[Vec2]) -> Vec<Vec2> {
+    let Some((i, j)) = (0..outer.len())
+        .flat_map(|i| (0..hole.len()).map(move |j| (i, j)))
+        .min_by(|(i1, j1), (i2, j2)| {
+            let distance1 = outer[*i1].distance_squared_to(hole[*j1]);
+            let distance2 = outer[*i2].distance_squared_to(hole[*j2]);
+            distance1.total_cmp(&distance2)
+        })
+    else {
+        return outer.to_vec();
+    };
+    let mut points = Vec::with_capacity(outer.len() + hole.len() + 2);
+    points.extend_from_slice(&outer[..=i]);
+    points.extend_from_slice(&hole[j..]);
+    points.extend_from_slice(&hole[..=j]);
+    points.extend_from_slice(&outer[i..]);
+    points
+}
+
+// Modify the supplied polygon to remove unnecessary points.
+// Tile data may not line up perfectly, resulting in e.g. two points
+// that are right next to each other.
+// In addition, even if they line up perfectly, due to tiling, there will be
+// intermediate points.
+// Note: Not exhaustive for all hypothetical polygons.
+pub fn smooth_polygon(polygon: &mut Vec<Vec2>) {
+    let remove_points = |polygon: &mut Vec<Vec2>, stack: &mut Vec<usize>| {
+        // Remove in reverse order so the indices are still correct.
+        // `0` can be at the end; ensure it is moved to the front.
+        stack.sort();
+        while let Some(index) = stack.pop() {
+            polygon.remove(index);
+        }
+    };
+
+    // In the first pass, remove points that are effectively the same point.
+    // This way the second pass doesn't need to consider these doubles.
+    let mut stack = Vec::new();
+    for i in 0..polygon.len() {
+        let i2 = next_point(polygon, i);
+        let a = polygon[i];
+        let b = polygon[i2];
+        if a.distance_squared_to(b) < 1.0 {
+            stack.push(i2);
+        }
+    }
+
+    remove_points(polygon, &mut stack);
+
+    // Combine adjacent segments that have approximately the same normals,
+    // resulting in longer continuous surfaces.
+    for i in 0..polygon.len() {
+        let i2 = next_point(polygon, i);
+        // If either normal is ambiguous, keep the point rather than guess.
+        if let Ok(Some(surface_ab)) = LandingSurface::find_surface(polygon, i, i2) {
+            let i3 = next_point(polygon, i2);
+            if let Ok(Some(surface_bc)) = LandingSurface::find_surface(polygon, i2, i3) {
+                if math::same_normals_approx(surface_ab.normal, surface_bc.normal) {
+                    stack.push(i2);
+                }
+            }
+        }
+    }
+
+    remove_points(polygon, &mut stack);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square() -> Vec<Vec2> {
+        vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(64.0, 0.0),
+            Vec2::new(64.0, 64.0),
+            Vec2::new(0.0, 64.0),
+        ]
+    }
+
+    #[test]
+    fn test_next_and_prior_point() {
+        let square = square();
+        assert_eq!(next_point(&square, 0), 1);
+        assert_eq!(next_point(&square, 3), 0);
+        assert_eq!(prior_point(&square, 0), 3);
+        assert_eq!(prior_point(&square, 2), 1);
+    }
+
+    #[test]
+    fn test_is_point_in_polygon() {
+        let square = square();
+        assert!(is_point_in_polygon(Vec2::new(32.0, 32.0), &square));
+        assert!(!is_point_in_polygon(Vec2::new(-1.0, 32.0), &square));
+        assert!(!is_point_in_polygon(Vec2::new(32.0, 65.0), &square));
+        // Level with a vertex.
+        assert!(!is_point_in_polygon(Vec2::new(-10.0, 0.0), &square));
+        assert!(!is_point_in_polygon(Vec2::new(70.0, 64.0), &square));
+
+        // Concave: a "U" shape.
+        let u = vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(10.0, 20.0),
+            Vec2::new(20.0, 20.0),
+            Vec2::new(20.0, 0.0),
+            Vec2::new(30.0, 0.0),
+            Vec2::new(30.0, 30.0),
+            Vec2::new(0.0, 30.0),
+        ];
+        assert!(is_point_in_polygon(Vec2::new(5.0, 10.0), &u));
+        assert!(!is_point_in_polygon(Vec2::new(15.0, 10.0), &u));
+        assert!(is_point_in_polygon(Vec2::new(15.0, 25.0), &u));
+    }
+
+    #[test]
+    fn test_distance_to_edges() {
+        let square = square();
+        assert_eq!(distance_to_edges(Vec2::new(32.0, 10.0), &square), 10.0);
+        assert_eq!(distance_to_edges(Vec2::new(70.0, 32.0), &square), 6.0);
+        assert_eq!(distance_to_edges(Vec2::new(67.0, 68.0), &square), 5.0);
+        assert_eq!(distance_to_edges(Vec2::new(0.0, 20.0), &square), 0.0);
+    }
+
+    #[test]
+    fn test_join_hole() {
+        let hole = vec![
+            Vec2::new(16.0, 16.0),
+            Vec2::new(48.0, 16.0),
+            Vec2::new(48.0, 48.0),
+            Vec2::new(16.0, 48.0),
+        ];
+        let joined = join_hole(&square(), &hole);
+        assert_eq!(joined.len(), 10);
+        assert!(is_point_in_polygon(Vec2::new(8.0, 32.0), &joined));
+        assert!(is_point_in_polygon(Vec2::new(56.0, 32.0), &joined));
+        assert!(is_point_in_polygon(Vec2::new(32.0, 8.0), &joined));
+        assert!(!is_point_in_polygon(Vec2::new(32.0, 32.0), &joined));
+        // Next to the bridge, from the top left corners.
+        assert!(is_point_in_polygon(Vec2::new(9.0, 8.0), &joined));
+        assert!(!is_point_in_polygon(Vec2::new(32.0, 65.0), &joined));
+        // The bridge runs from the outer corner at 0 to the hole's at 1.
+        assert!(LandingSurface::find_surface(&joined, 0, 1).is_err());
+    }
+
+    #[test]
+    fn test_smooth_polygon_removes_doubles_and_collinear_points() {
+        let mut polygon = vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(32.0, 0.0),
+            Vec2::new(64.0, 0.0),
+            Vec2::new(64.0, 64.0),
+            Vec2::new(64.2, 64.1),
+            Vec2::new(0.0, 64.0),
+        ];
+        smooth_polygon(&mut polygon);
+        assert_eq!(polygon, square());
+    }
+}
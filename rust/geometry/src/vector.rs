@@ -0,0 +1,137 @@
+use std::fmt::Display;
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub};
+
+/// 2D vector, mirroring the parts of Godot's `Vector2` needed for landing.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Vec2 {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Vec2 {
+    pub const ZERO: Vec2 = Vec2::new(0.0, 0.0);
+
+    pub const fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+
+    pub fn dot(self, other: Vec2) -> f32 {
+        self.x * other.x + self.y * other.y
+    }
+
+    pub fn cross(self, other: Vec2) -> f32 {
+        self.x * other.y - self.y * other.x
+    }
+
+    pub fn length_squared(self) -> f32 {
+        self.dot(self)
+    }
+
+    pub fn length(self) -> f32 {
+        self.length_squared().sqrt()
+    }
+
+    pub fn distance_squared_to(self, other: Vec2) -> f32 {
+        (other - self).length_squared()
+    }
+
+    pub fn distance_to(self, other: Vec2) -> f32 {
+        (other - self).length()
+    }
+
+    // Matches Godot: rotated 90 degrees counter-clockwise in Godot's y-down
+    // coordinates.
+    pub fn orthogonal(self) -> Self {
+        Vec2::new(self.y, -self.x)
+    }
+
+    // Project onto `other`.
+    pub fn project(self, other: Vec2) -> Self {
+        other * (self.dot(other) / other.length_squared())
+    }
+
+    // Unlike Godot, also returns `None` for vectors that cannot be normalized
+    // because they are not finite.
+    pub fn try_normalized(self) -> Option<Self> {
+        let length = self.length();
+        if length == 0.0 || !length.is_finite() {
+            return None;
+        }
+        Some(self / length)
+    }
+
+    pub fn angle(self) -> f32 {
+        self.y.atan2(self.x)
+    }
+
+    pub fn angle_to(self, to: Vec2) -> f32 {
+        self.cross(to).atan2(self.dot(to))
+    }
+
+    pub fn is_zero_approx(self) -> bool {
+        // Godot's `CMP_EPSILON`.
+        const EPSILON: f32 = 0.00001;
+        self.x.abs() < EPSILON && self.y.abs() < EPSILON
+    }
+}
+
+impl Add for Vec2 {
+    type Output = Self;
+
+    fn add(self, other: Vec2) -> Self {
+        Vec2::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl AddAssign for Vec2 {
+    fn add_assign(&mut self, other: Vec2) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Vec2 {
+    type Output = Self;
+
+    fn sub(self, other: Vec2) -> Self {
+        Vec2::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl Neg for Vec2 {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Vec2::new(-self.x, -self.y)
+    }
+}
+
+impl Mul<f32> for Vec2 {
+    type Output = Self;
+
+    fn mul(self, scalar: f32) -> Self {
+        Vec2::new(self.x * scalar, self.y * scalar)
+    }
+}
+
+impl Mul<Vec2> for f32 {
+    type Output = Vec2;
+
+    fn mul(self, vector: Vec2) -> Vec2 {
+        vector * self
+    }
+}
+
+impl Div<f32> for Vec2 {
+    type Output = Self;
+
+    fn div(self, scalar: f32) -> Self {
+        Vec2::new(self.x / scalar, self.y / scalar)
+    }
+}
+
+// Same format as Godot, so logs look the same.
+impl Display for Vec2 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
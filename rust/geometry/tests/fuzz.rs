@@ -0,0 +1,261 @@
+// Property-based tests over random polygons shaped like those produced by
+// merging tiles. On failure, the polygons are printed as `PackedVector2Array`s
+// that can be pasted into a `CollisionPolygon2D` or a tile's collision polygon
+// to build a test scene (see `godot/test_scenes/README.md`).
+
+use geometry::polygon::{distance_to_segment, next_point, smooth_polygon};
+use geometry::{LandingSurface, Vec2};
+use proptest::prelude::*;
+
+const TILE_SIZE: f32 = 64.0;
+
+// Matches the branches in `tile_set.tres`.
+const BRANCH_TOP: f32 = -9.5;
+const BRANCH_BOTTOM: f32 = -2.75;
+
+// Tiles that were merged, before any transformation.
+#[derive(Debug, Clone)]
+enum Shape {
+    // Adjacent columns of solid tiles, standing on the same row. Heights are in
+    // tiles and the top of a column is sloped when its sides differ.
+    Columns(Vec<(u8, u8)>),
+    // A horizontal branch spanning this many tiles.
+    Branch(u8),
+}
+
+#[derive(Debug, Clone)]
+struct TileMergedPolygon {
+    shape: Shape,
+    // Offsets applied to each point in turn (wrapping around), to simulate
+    // tiles that do not line up perfectly.
+    jitter: Vec<(f32, f32)>,
+    // Number of 90 degree rotations, so that walls and ceilings are covered.
+    rotations: u8,
+    // Whether to wind the points in the opposite direction.
+    reversed: bool,
+    // Map coordinates of the first tile.
+    origin: (i8, i8),
+}
+
+impl TileMergedPolygon {
+    fn points(&self) -> Vec<Vec2> {
+        let mut points = match &self.shape {
+            Shape::Columns(columns) => columns_outline(columns),
+            Shape::Branch(tiles) => branch_outline(*tiles),
+        };
+        if self.reversed {
+            points.reverse();
+        }
+        let origin = Vec2::new(self.origin.0 as f32, self.origin.1 as f32) * TILE_SIZE;
+        for (i, point) in points.iter_mut().enumerate() {
+            let (x, y) = self.jitter[i % self.jitter.len()];
+            let mut transformed = *point + Vec2::new(x, y);
+            for _ in 0..self.rotations {
+                transformed = Vec2::new(-transformed.y, transformed.x);
+            }
+            *point = transformed + origin;
+        }
+        points
+    }
+}
+
+// Includes a point at every tile boundary, as well as duplicated points where
+// the tops of adjacent columns meet, as `Geometry2D::merge_polygons` can leave
+// behind.
+fn columns_outline(columns: &[(u8, u8)]) -> Vec<Vec2> {
+    let height = |tiles: u8| -(tiles as f32) * TILE_SIZE;
+    let width = columns.len() as f32 * TILE_SIZE;
+    let mut points = Vec::new();
+
+    let first_height = columns[0].0;
+    for tile in 0..first_height {
+        points.push(Vec2::new(0.0, height(tile)));
+    }
+    for (i, (left, right)) in columns.iter().enumerate() {
+        let x = i as f32 * TILE_SIZE;
+        points.push(Vec2::new(x, height(*left)));
+        points.push(Vec2::new(x + TILE_SIZE, height(*right)));
+    }
+    let last_height = columns[columns.len() - 1].1;
+    for tile in (1..last_height).rev() {
+        points.push(Vec2::new(width, height(tile)));
+    }
+    for i in (1..=columns.len()).rev() {
+        points.push(Vec2::new(i as f32 * TILE_SIZE, 0.0));
+    }
+    points
+}
+
+// Includes both of the points at each seam between tiles.
+fn branch_outline(tiles: u8) -> Vec<Vec2> {
+    let mut points = vec![Vec2::new(0.0, BRANCH_TOP)];
+    for tile in 1..tiles {
+        let x = tile as f32 * TILE_SIZE;
+        points.push(Vec2::new(x, BRANCH_TOP));
+        points.push(Vec2::new(x, BRANCH_TOP));
+    }
+    points.push(Vec2::new(tiles as f32 * TILE_SIZE, BRANCH_TOP));
+    points.push(Vec2::new(tiles as f32 * TILE_SIZE, BRANCH_BOTTOM));
+    for tile in (1..tiles).rev() {
+        let x = tile as f32 * TILE_SIZE;
+        points.push(Vec2::new(x, BRANCH_BOTTOM));
+        points.push(Vec2::new(x, BRANCH_BOTTOM));
+    }
+    points.push(Vec2::new(0.0, BRANCH_BOTTOM));
+    points
+}
+
+fn shape() -> impl Strategy<Value = Shape> {
+    // The sides of a column differ by at most one tile.
+    let column = (1u8..=4).prop_flat_map(|left| (Just(left), left.max(2) - 1..=(left + 1).min(4)));
+    prop_oneof![
+        prop::collection::vec(column, 1..=6).prop_map(Shape::Columns),
+        (1u8..=4).prop_map(Shape::Branch),
+    ]
+}
+
+fn tile_merged_polygon() -> impl Strategy<Value = TileMergedPolygon> {
+    let offset = prop_oneof![Just(0.0f32), -0.3f32..0.3];
+    (
+        shape(),
+        prop::collection::vec((offset.clone(), offset), 1..8),
+        0u8..4,
+        any::<bool>(),
+        (any::<i8>(), any::<i8>()),
+    )
+        .prop_map(
+            |(shape, jitter, rotations, reversed, origin)| TileMergedPolygon {
+                shape,
+                jitter,
+                rotations,
+                reversed,
+                origin,
+            },
+        )
+}
+
+// e.g. "PackedVector2Array(0, 0, 64, 0, 64, 64)"
+fn packed_vector2_array(polygon: &[Vec2]) -> String {
+    let coordinates: Vec<String> = polygon
+        .iter()
+        .flat_map(|point| [point.x.to_string(), point.y.to_string()])
+        .collect();
+    format!("PackedVector2Array({})", coordinates.join(", "))
+}
+
+// Twice the signed area of `polygon`, by the shoelace formula. Its sign gives
+// the polygon's winding, independently of `is_point_in_polygon`.
+fn signed_area(polygon: &[Vec2]) -> f32 {
+    (0..polygon.len())
+        .map(|i| polygon[i].cross(polygon[next_point(polygon, i)]))
+        .sum()
+}
+
+fn segments_intersect(a: Vec2, b: Vec2, c: Vec2, d: Vec2) -> bool {
+    const TOLERANCE: f32 = 0.01;
+    let side = |p: Vec2, q: Vec2, r: Vec2| (q - p).cross(r - p);
+    let crosses = |s1: f32, s2: f32| (s1 > 0.0 && s2 < 0.0) || (s1 < 0.0 && s2 > 0.0);
+    if crosses(side(a, b, c), side(a, b, d)) && crosses(side(c, d, a), side(c, d, b)) {
+        return true;
+    }
+    distance_to_segment(a, c, d) < TOLERANCE
+        || distance_to_segment(b, c, d) < TOLERANCE
+        || distance_to_segment(c, a, b) < TOLERANCE
+        || distance_to_segment(d, a, b) < TOLERANCE
+}
+
+// Returns a description of the first problem found, if any.
+fn find_self_intersection(polygon: &[Vec2]) -> Option<String> {
+    let len = polygon.len();
+    for i in 0..len {
+        let a = polygon[i];
+        let b = polygon[next_point(polygon, i)];
+        // Adjacent edges share a point, so only check that they don't double
+        // back on each other.
+        let c = polygon[next_point(polygon, next_point(polygon, i))];
+        if (a - b).angle_to(c - b).abs() < 0.01 {
+            return Some(format!("{a}, {b}, {c} double back"));
+        }
+        for j in (i + 2)..len {
+            if i == 0 && j == len - 1 {
+                continue;
+            }
+            let c = polygon[j];
+            let d = polygon[next_point(polygon, j)];
+            if segments_intersect(a, b, c, d) {
+                return Some(format!("{a}-{b} intersects {c}-{d}"));
+            }
+        }
+    }
+    None
+}
+
+proptest! {
+    #[test]
+    fn smoothing_leaves_no_short_edges(polygon in tile_merged_polygon()) {
+        let original = polygon.points();
+        let mut smoothed = original.clone();
+        smooth_polygon(&mut smoothed);
+        prop_assert!(
+            smoothed.len() >= 3,
+            "Smoothed {} to {}",
+            packed_vector2_array(&original),
+            packed_vector2_array(&smoothed)
+        );
+        for i in 0..smoothed.len() {
+            let a = smoothed[i];
+            let b = smoothed[next_point(&smoothed, i)];
+            prop_assert!(
+                a.distance_to(b) >= 1.0,
+                "Edge {a}-{b} is too short after smoothing {} to {}",
+                packed_vector2_array(&original),
+                packed_vector2_array(&smoothed)
+            );
+        }
+    }
+
+    #[test]
+    fn smoothing_leaves_no_self_intersections(polygon in tile_merged_polygon()) {
+        let original = polygon.points();
+        let mut smoothed = original.clone();
+        smooth_polygon(&mut smoothed);
+        if let Some(problem) = find_self_intersection(&smoothed) {
+            prop_assert!(
+                false,
+                "{problem} after smoothing {} to {}",
+                packed_vector2_array(&original),
+                packed_vector2_array(&smoothed)
+            );
+        }
+    }
+
+    #[test]
+    fn corrected_normals_point_out(polygon in tile_merged_polygon(), flip in any::<bool>()) {
+        let mut smoothed = polygon.points();
+        smooth_polygon(&mut smoothed);
+        for i in 0..smoothed.len() {
+            let a = smoothed[i];
+            let b = smoothed[next_point(&smoothed, i)];
+            let Some(normal) = (a - b).orthogonal().try_normalized() else {
+                continue;
+            };
+            let normal = if flip { -normal } else { normal };
+            let surface = LandingSurface { a, b, normal };
+            let corrected = surface.correct_normal(&smoothed);
+            prop_assert!(
+                corrected.is_ok(),
+                "Ambiguous normal for {a}-{b} in {}: {corrected:?}",
+                packed_vector2_array(&smoothed)
+            );
+            let corrected = corrected.unwrap();
+            // An outward normal is on the side of each edge away from the
+            // interior, which the winding determines.
+            prop_assert!(
+                (b - a).cross(corrected.normal) * signed_area(&smoothed) < 0.0,
+                "Normal {} for {a}-{b} points into {}",
+                corrected.normal,
+                packed_vector2_array(&smoothed)
+            );
+        }
+    }
+}
@@ -0,0 +1,464 @@
+// Cases covered by hand in `godot/test_scenes`. Polygons are built from the
+// collision polygons in `tile_set.tres`, merged as `Geometry2D::merge_polygons`
+// would merge adjacent tiles. Tiles are 64x64, so a tile at map coordinates
+// (x, y) is offset by (64 * x, 64 * y).
+
+use geometry::polygon::{is_point_in_polygon, smooth_polygon};
+use geometry::{
+    find_corner_shimmy, pick_side_to_land_on, pick_side_to_land_on_from_corner, Lander,
+    LandingError, LandingSurface, Vec2,
+};
+
+// The player's collision rectangle is 38 wide, and `WIDTH_MODIFIER` is 0.7.
+const MIN_SURFACE_LENGTH: f32 = 38.0 * 0.7;
+
+fn polygon(points: &[(f32, f32)]) -> Vec<Vec2> {
+    points.iter().map(|(x, y)| Vec2::new(*x, *y)).collect()
+}
+
+fn smoothed(points: &[(f32, f32)]) -> Vec<Vec2> {
+    let mut polygon = polygon(points);
+    smooth_polygon(&mut polygon);
+    polygon
+}
+
+fn lander(x: f32, y: f32) -> Lander {
+    Lander {
+        position: Vec2::new(x, y),
+        min_surface_length: MIN_SURFACE_LENGTH,
+    }
+}
+
+fn assert_normal(surface: &LandingSurface, expected: Vec2) {
+    assert!(
+        surface.normal.angle_to(expected).abs() < 0.05,
+        "Expected normal {expected}, found {:?}",
+        surface
+    );
+}
+
+// The normal should point away from the polygon.
+fn assert_points_out(surface: &LandingSurface, polygon: &[Vec2]) {
+    let outside = (surface.a + surface.b) / 2.0 + surface.normal;
+    assert!(
+        !is_point_in_polygon(outside, polygon),
+        "Normal points into the polygon: {surface:?}"
+    );
+}
+
+// The end of the branch in tile 2:1, merged with the branch in tile 3:1 at the
+// next map coordinate to the right. The two tiles do not line up perfectly at
+// the seam (x = 32).
+fn branch() -> Vec<Vec2> {
+    smoothed(&[
+        (-6.6875, -9.4375),
+        (32.0, -9.5),
+        (96.0, -9.5),
+        (96.0, -2.75),
+        (32.0, -2.5),
+        (32.0, -2.75),
+        (-6.75, -2.75),
+    ])
+}
+
+#[test]
+fn test_branch_smoothing() {
+    // The seam points are removed, leaving the four corners of the branch.
+    assert_eq!(
+        branch(),
+        polygon(&[
+            (-6.6875, -9.4375),
+            (96.0, -9.5),
+            (96.0, -2.75),
+            (-6.75, -2.75),
+        ])
+    );
+}
+
+// test_branch_tip: Landing directly on a surface that is too small for the
+// player (the tip of a branch) should move the player onto another side.
+#[test]
+fn test_branch_tip() {
+    let branch = branch();
+    let tip_top = branch[0];
+    let tip_bottom = branch[3];
+    let collision_position = (tip_top + tip_bottom) / 2.0;
+    let collision_normal = (tip_top - tip_bottom)
+        .orthogonal()
+        .try_normalized()
+        .unwrap();
+    assert_normal(
+        &LandingSurface {
+            a: tip_top,
+            b: tip_bottom,
+            normal: collision_normal,
+        },
+        Vec2::new(-1.0, 0.0),
+    );
+
+    // The player is above the middle of the tip, so the top is closer.
+    let surface = pick_side_to_land_on(
+        &lander(-26.0, -12.0),
+        &branch,
+        collision_position,
+        Vec2::new(5.0, 1.0),
+        collision_normal,
+    )
+    .expect("Normal should not be ambiguous")
+    .expect("Should find a surface");
+    assert_eq!(surface.a, tip_top);
+    assert_normal(&surface, Vec2::new(0.0, -1.0));
+    assert_points_out(&surface, &branch);
+
+    // Below the middle, the bottom is closer.
+    let surface = pick_side_to_land_on(
+        &lander(-26.0, 1.0),
+        &branch,
+        collision_position,
+        Vec2::new(5.0, -1.0),
+        collision_normal,
+    )
+    .expect("Normal should not be ambiguous")
+    .expect("Should find a surface");
+    assert_eq!(surface.a, tip_bottom);
+    assert_normal(&surface, Vec2::new(0.0, 1.0));
+    assert_points_out(&surface, &branch);
+}
+
+// test_branch_tip2: Colliding with the *bottom* corner of the branch. The
+// normal computed from the player's motion points into the branch, and must be
+// corrected so the player ends up underneath it.
+#[test]
+fn test_branch_tip2() {
+    let branch = branch();
+    let surface = pick_side_to_land_on_from_corner(
+        &lander(-20.0, -14.0),
+        &branch,
+        3,
+        Vec2::new(4.0, 4.0),
+        Vec2::new(-0.9, 0.4),
+    )
+    .expect("Should land on the bottom of the branch");
+    assert_eq!(surface.a, branch[3]);
+    assert_eq!(surface.b, branch[2]);
+    assert_normal(&surface, Vec2::new(0.0, 1.0));
+    assert_points_out(&surface, &branch);
+}
+
+// test_corner / test_branch_tip3: When landing on a corner with one side that
+// is too small to land on, land on the larger side, even if the smaller side
+// better matches the collision.
+#[test]
+fn test_corner() {
+    let branch = branch();
+    let surface = pick_side_to_land_on_from_corner(
+        &lander(-20.0, -14.0),
+        &branch,
+        0,
+        Vec2::new(4.0, 2.0),
+        Vec2::new(-1.0, 0.0),
+    )
+    .expect("Should land on the top of the branch");
+    assert_eq!(surface.a, branch[0]);
+    assert_eq!(surface.b, branch[1]);
+    assert_normal(&surface, Vec2::new(0.0, -1.0));
+    assert_points_out(&surface, &branch);
+}
+
+// A corner where neither side is large enough.
+#[test]
+fn test_corner_too_small() {
+    let sliver = polygon(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]);
+    let result = pick_side_to_land_on_from_corner(
+        &lander(-5.0, -5.0),
+        &sliver,
+        0,
+        Vec2::new(1.0, 1.0),
+        Vec2::new(-0.7, -0.7),
+    );
+    assert!(matches!(result, Err(LandingError::CouldNotLandAnywhere)));
+}
+
+#[test]
+fn test_corner_no_motion() {
+    let branch = branch();
+    let result = pick_side_to_land_on_from_corner(
+        &lander(-20.0, -14.0),
+        &branch,
+        0,
+        Vec2::ZERO,
+        Vec2::new(0.0, -1.0),
+    );
+    assert!(matches!(result, Err(LandingError::NoPlayerMotion)));
+}
+
+// A short twig sticking out of the left side of a trunk. The tip and the top
+// and bottom of the twig are all too small for the player.
+fn twig() -> Vec<Vec2> {
+    polygon(&[
+        (-20.0, -3.0),
+        (0.0, -3.0),
+        (0.0, -60.0),
+        (30.0, -60.0),
+        (30.0, 60.0),
+        (0.0, 60.0),
+        (0.0, 3.0),
+        (-20.0, 3.0),
+    ])
+}
+
+// Landing on the tip of a twig should shimmy around the corners of the twig to
+// the trunk, going over or under the twig, whichever is closer.
+#[test]
+fn test_corner_shimmy() {
+    let twig = twig();
+    let tip = LandingSurface {
+        a: twig[7],
+        b: twig[0],
+        normal: Vec2::new(-1.0, 0.0),
+    };
+
+    let shimmy = find_corner_shimmy(&lander(-30.0, -8.0), &twig, &tip)
+        .expect("Normal should not be ambiguous")
+        .expect("Should shimmy over the twig");
+    assert_eq!(shimmy.corners, vec![twig[0], twig[1]]);
+    assert_eq!(shimmy.surface.a, twig[1]);
+    assert_eq!(shimmy.surface.b, twig[2]);
+    assert_normal(&shimmy.surface, Vec2::new(-1.0, 0.0));
+
+    let shimmy = find_corner_shimmy(&lander(-30.0, 8.0), &twig, &tip)
+        .expect("Normal should not be ambiguous")
+        .expect("Should shimmy under the twig");
+    assert_eq!(shimmy.corners, vec![twig[7], twig[6]]);
+    assert_eq!(shimmy.surface.a, twig[6]);
+    assert_eq!(shimmy.surface.b, twig[5]);
+    assert_normal(&shimmy.surface, Vec2::new(-1.0, 0.0));
+}
+
+// Nowhere nearby to shimmy to.
+#[test]
+fn test_corner_shimmy_too_small() {
+    let sliver = polygon(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]);
+    let surface = LandingSurface {
+        a: sliver[0],
+        b: sliver[1],
+        normal: Vec2::new(0.0, -1.0),
+    };
+    assert_eq!(
+        find_corner_shimmy(&lander(5.0, -5.0), &sliver, &surface)
+            .expect("Normal should not be ambiguous"),
+        None
+    );
+}
+
+// Three of tile 3:3 in a row.
+fn ground() -> Vec<Vec2> {
+    smoothed(&[
+        (-32.0, -13.0),
+        (32.0, -13.0),
+        (96.0, -13.0),
+        (160.0, -13.0),
+        (160.0, 32.0),
+        (96.0, 32.0),
+        (32.0, 32.0),
+        (-32.0, 32.0),
+    ])
+}
+
+// test_corner_seam_up: Landing on a seam between two tiles. The seam is not a
+// corner of the smoothed polygon, so the player lands on the continuous
+// surface.
+#[test]
+fn test_corner_seam_up() {
+    let ground = ground();
+    assert_eq!(
+        ground,
+        polygon(&[(-32.0, -13.0), (160.0, -13.0), (160.0, 32.0), (-32.0, 32.0)])
+    );
+
+    let seam = Vec2::new(32.0, -13.0);
+    assert!(!ground.contains(&seam));
+    let surface = pick_side_to_land_on(
+        &lander(32.0, -30.0),
+        &ground,
+        seam,
+        Vec2::new(2.0, 4.0),
+        Vec2::new(0.0, -1.0),
+    )
+    .expect("Normal should not be ambiguous")
+    .expect("Should land on the top");
+    assert_eq!(surface.a, ground[0]);
+    assert_eq!(surface.b, ground[1]);
+    assert_normal(&surface, Vec2::new(0.0, -1.0));
+}
+
+// test_corner_seam_left: Landing directly on the corner of merged tiles.
+#[test]
+fn test_corner_seam_left() {
+    let ground = ground();
+    let surface = pick_side_to_land_on_from_corner(
+        &lander(-45.0, -20.0),
+        &ground,
+        0,
+        Vec2::new(4.0, 4.0),
+        Vec2::new(-0.3, -0.95),
+    )
+    .expect("Should land on the top");
+    assert_eq!(surface.b, ground[1]);
+    assert_normal(&surface, Vec2::new(0.0, -1.0));
+}
+
+// The branch in tile 3:1 merged with the trunk in tile 4:1 to its right, and
+// the trunk in tile 1:2 above that. Neither the branch stub on the trunk nor
+// the trunk above quite line up.
+fn nook() -> Vec<Vec2> {
+    smoothed(&[
+        (-32.0, -9.5),
+        (32.0, -9.5),
+        (32.0, -9.50175),
+        (50.2998, -9.50175),
+        (50.2998, -32.0),
+        (50.125, -32.0),
+        (50.125, -73.5),
+        (77.875, -73.5),
+        (77.625, -32.0),
+        (77.7886, -32.0),
+        (77.7886, 14.0),
+        (96.0, 14.0),
+        (96.0, 18.75),
+        (77.6118, 18.75),
+        (77.625, 32.0),
+        (50.2114, 32.0),
+        (50.2114, -2.91682),
+        (32.0, -2.91682),
+        (32.0, -2.75),
+        (-32.0, -2.5),
+    ])
+}
+
+// test_nook: Landing in the corner between the branch and the trunk.
+#[test]
+fn test_nook() {
+    let nook = nook();
+    // The near duplicate points at the seams are removed, as are the points
+    // between nearly parallel segments.
+    assert_eq!(nook.len(), 12);
+    let corner = Vec2::new(50.2998, -9.50175);
+    let index = nook
+        .iter()
+        .position(|point| *point == corner)
+        .expect("Nook corner should remain");
+
+    // Jumping to the right, the player hits the trunk.
+    let surface = pick_side_to_land_on_from_corner(
+        &lander(30.0, -20.0),
+        &nook,
+        index,
+        Vec2::new(4.0, 1.0),
+        Vec2::new(-1.0, 0.0),
+    )
+    .expect("Should land on the trunk");
+    assert_eq!(surface.a, corner);
+    assert_normal(&surface, Vec2::new(-1.0, 0.0));
+    assert_points_out(&surface, &nook);
+
+    // Falling, the player lands on the branch.
+    let surface = pick_side_to_land_on_from_corner(
+        &lander(40.0, -30.0),
+        &nook,
+        index,
+        Vec2::new(1.0, 4.0),
+        Vec2::new(0.0, -1.0),
+    )
+    .expect("Should land on the branch");
+    assert_eq!(surface.a, corner);
+    assert_normal(&surface, Vec2::new(0.0, -1.0));
+    assert_points_out(&surface, &nook);
+}
+
+// test_wedge_corner: Landing on the top right corner of a slab (tile 0:0) next
+// to a gap. The right side is too small to land on, and its normal computed
+// from the player's motion points into the slab.
+#[test]
+fn test_wedge_corner() {
+    let slab = polygon(&[(-32.0, 7.5), (32.0, 7.5), (32.0, 32.0), (-32.0, 32.0)]);
+    let surface = pick_side_to_land_on_from_corner(
+        &lander(20.0, -10.0),
+        &slab,
+        1,
+        Vec2::new(4.0, 4.0),
+        Vec2::new(1.0, 0.0),
+    )
+    .expect("Should land on the top");
+    assert_eq!(surface.a, slab[1]);
+    assert_eq!(surface.b, slab[0]);
+    assert_normal(&surface, Vec2::new(0.0, -1.0));
+}
+
+// A corner where the side that best matches the collision has a normal
+// pointing into the polygon, based on the player's motion, and must be
+// corrected.
+#[test]
+fn test_wedge_normal_corrected() {
+    let wedge = polygon(&[(0.0, 0.0), (64.0, 64.0), (0.0, 64.0)]);
+    let surface = pick_side_to_land_on_from_corner(
+        &lander(-10.0, -10.0),
+        &wedge,
+        0,
+        Vec2::new(-4.0, 4.0),
+        Vec2::new(0.9, -0.2),
+    )
+    .expect("Should land on the left side");
+    assert_eq!(surface.b, wedge[2]);
+    assert_normal(&surface, Vec2::new(-1.0, 0.0));
+    assert_points_out(&surface, &wedge);
+}
+
+// A slit into a polygon has the polygon on both sides, so there is no telling
+// which way its normal should point.
+#[test]
+fn test_slit_normal_ambiguous() {
+    let slit = polygon(&[
+        (0.0, 0.0),
+        (20.0, 0.0),
+        (20.0, 10.0),
+        (10.0, 10.0),
+        (10.0, 5.0),
+        (10.0, 10.0),
+        (0.0, 10.0),
+    ]);
+    let ambiguous =
+        LandingSurface::find_surface(&slit, 3, 4).expect_err("Normal should be ambiguous");
+    assert_eq!(ambiguous.surface.a, slit[3]);
+    assert_eq!(ambiguous.surface.b, slit[4]);
+    for test_point in ambiguous.test_points {
+        assert!(is_point_in_polygon(test_point, &slit));
+    }
+}
+
+// Landing on a side too small for the player, next to the slit, should skip
+// the slit's ambiguous side and land on the other neighbor.
+#[test]
+fn test_ambiguous_neighbor_skipped() {
+    let slit = polygon(&[
+        (0.0, -60.0),
+        (20.0, -60.0),
+        (20.0, 10.0),
+        (10.0, 10.0),
+        (10.0, 5.0),
+        (10.0, 10.0),
+        (0.0, 10.0),
+    ]);
+    let surface = pick_side_to_land_on(
+        &lander(15.0, 30.0),
+        &slit,
+        Vec2::new(15.0, 10.0),
+        Vec2::new(0.0, -5.0),
+        Vec2::new(0.0, 1.0),
+    )
+    .expect("Normal should not be ambiguous")
+    .expect("Should land on the right side");
+    assert_eq!(surface.a, slit[2]);
+    assert_eq!(surface.b, slit[1]);
+    assert_normal(&surface, Vec2::new(1.0, 0.0));
+}
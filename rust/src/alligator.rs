@@ -1,6 +1,7 @@
 use std::fmt::Display;
 
 use crate::log;
+use crate::player::Player;
 
 use godot::classes::{AnimatedSprite2D, Area2D, Node2D, Timer};
 use godot::global::{absf, clampf, maxf, randf, randf_range};
@@ -161,8 +162,7 @@ impl INode2D for Alligator {
                     // animation, so I think I'd need to connect/disconnect the
                     // signal.
                     self.state = State::Idle;
-                    self.base_mut()
-                        .emit_signal("player_eaten", &[player.to_variant()]);
+                    self.bite(player);
                     self.animate("raise_eyebrows", true);
                 }
             }
@@ -175,6 +175,29 @@ impl INode2D for Alligator {
 impl Alligator {
     #[signal]
     fn player_eaten(player: Gd<Node2D>);
+    #[signal]
+    fn player_damaged(player: Gd<Node2D>, hit_direction: Vector2);
+
+    // Eat the player, or only damage them if they have health to spare. The
+    // `Level` decides whether the last hit eats them.
+    fn bite(&mut self, player: Gd<Node2D>) {
+        let has_health = player
+            .clone()
+            .try_cast::<Player>()
+            .is_ok_and(|player| player.bind().has_health());
+        if has_health {
+            let hit_direction = (player.get_global_position() - self.base().get_global_position())
+                .try_normalized()
+                .unwrap_or(Vector2::UP);
+            self.base_mut().emit_signal(
+                "player_damaged",
+                &[player.to_variant(), hit_direction.to_variant()],
+            );
+        } else {
+            self.base_mut()
+                .emit_signal("player_eaten", &[player.to_variant()]);
+        }
+    }
 
     fn upper_jaw(&self) -> Gd<AnimatedSprite2D> {
         self.base()
@@ -1,3 +1,4 @@
+use crate::report_error;
 use godot::classes::{Control, IControl};
 use godot::global::deg_to_rad;
 use godot::prelude::*;
@@ -42,7 +43,7 @@ impl IControl for Arrow {
         let length = self.end.length() / 6.0;
         let chevron_base = (Vector2::ZERO - self.end).normalized_or_zero() * length;
         if chevron_base == Vector2::ZERO {
-            godot_error!("Arrow has no chevron!");
+            report_error!("Arrow has no chevron!");
         }
         let angle = deg_to_rad(self.chevron_degrees as f64) as f32;
         let chevron_a = chevron_base.rotated(-angle) + self.end;
@@ -1,3 +1,4 @@
+use crate::report_error;
 use godot::classes::{AnimatedSprite2D, Control, IControl, Os, Timer};
 use godot::prelude::*;
 
@@ -50,7 +51,7 @@ impl IControl for ButtonHint {
 impl ButtonHint {
     pub fn set_pressed(&mut self, pressed: bool) {
         if self.autoplay {
-            godot_error!("ButtonHint::set_pressed should not be called when autoplaying!");
+            report_error!("ButtonHint::set_pressed should not be called when autoplaying!");
         }
         self.set_pressed_internal(pressed);
     }
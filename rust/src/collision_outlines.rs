@@ -0,0 +1,341 @@
+use std::collections::HashMap;
+use std::f32::consts::{PI, TAU};
+
+use crate::conversions::{to_polygon, to_vec2, to_vector2};
+use crate::log;
+use crate::report_error;
+use geometry::polygon::{distance_to_edges, join_hole, smooth_polygon};
+use geometry::Vec2;
+use godot::classes::{
+    CapsuleShape2D, CircleShape2D, CollisionPolygon2D, CollisionShape2D, ConvexPolygonShape2D,
+    Geometry2D, RectangleShape2D, TileMapLayer,
+};
+use godot::prelude::*;
+
+// Target length (in pixels) of the edges approximating curved shapes. This is
+// long enough for the player to land on a single edge, so that they can land
+// anywhere on a circle, for example, as long as it is not too small.
+const CURVE_EDGE_LENGTH: f32 = 32.0;
+
+// How far (in pixels) a collision may be from an outline's edge and still be
+// considered part of it. Smoothing can move edges slightly.
+const MAX_DISTANCE_FROM_EDGE: f32 = 2.0;
+
+// How far (in pixels) to grow bounding rects before checking whether they
+// overlap, so that polygons that only share an edge are still merged.
+const TOUCH_MARGIN: f32 = 1.0;
+
+// The merged collision polygons of connected tiles.
+struct Outline {
+    // Smoothed, in the layer's local coordinates.
+    points: Vec<Vec2>,
+    // Gaps enclosed by the outline, smoothed likewise. The landing code would
+    // treat the inside of a hole as solid, so a collision with a hole's edge
+    // uses the outline joined to that hole (see `join_hole`).
+    holes: Vec<Vec<Vec2>>,
+}
+
+/// Every collision polygon in a `TileMapLayer`, merged with those of adjacent
+/// tiles and smoothed once, rather than on each collision.
+pub struct CollisionOutlines {
+    collision_mask: u32,
+    outlines: Vec<Outline>,
+    // Spatial index from map coordinates to the outlines of that cell's tile.
+    outlines_by_cell: HashMap<Vector2i, Vec<usize>>,
+}
+
+impl CollisionOutlines {
+    pub fn build(
+        tile_map_layer: &Gd<TileMapLayer>,
+        collision_mask: u32,
+        debug_collisions: bool,
+    ) -> Self {
+        let mut polygons = Vec::new();
+        let mut polygon_cells = Vec::new();
+        for cell in tile_map_layer.get_used_cells().iter_shared() {
+            for polygon in tile_polygons(tile_map_layer, cell, collision_mask) {
+                polygons.push(polygon);
+                polygon_cells.push(cell);
+            }
+        }
+
+        let mut outlines = Vec::new();
+        let mut outlines_by_cell: HashMap<Vector2i, Vec<usize>> = HashMap::new();
+        for merged in merge_touching_polygons(polygons) {
+            let mut polygon = to_polygon(&merged.points);
+            smooth_polygon(&mut polygon);
+            let holes: Vec<_> = merged
+                .holes
+                .iter()
+                .map(|hole| {
+                    let mut hole = to_polygon(hole);
+                    smooth_polygon(&mut hole);
+                    hole
+                })
+                .collect();
+            log!(
+                debug_collisions,
+                "Cached outline: {polygon:?} with holes {holes:?}"
+            );
+            for source in merged.sources {
+                let indices = outlines_by_cell.entry(polygon_cells[source]).or_default();
+                if !indices.contains(&outlines.len()) {
+                    indices.push(outlines.len());
+                }
+            }
+            outlines.push(Outline {
+                points: polygon,
+                holes,
+            });
+        }
+        CollisionOutlines {
+            collision_mask,
+            outlines,
+            outlines_by_cell,
+        }
+    }
+
+    // The mask the outlines were built for.
+    pub fn collision_mask(&self) -> u32 {
+        self.collision_mask
+    }
+
+    // Return the outline (in global coordinates) that includes
+    // `collision_position`, if any. `None` means the caller should merge the
+    // tiles itself.
+    pub fn find(
+        &self,
+        tile_map_layer: &Gd<TileMapLayer>,
+        collision_position: Vector2,
+    ) -> Option<Vec<Vec2>> {
+        let local_collision = tile_map_layer.to_local(collision_position);
+        let map_coordinates = tile_map_layer.local_to_map(local_collision);
+        // A collision on the edge of a tile may map to the neighboring cell,
+        // which can be empty, so check the surrounding cells as well.
+        let mut cells = vec![map_coordinates];
+        cells.extend(
+            tile_map_layer
+                .get_surrounding_cells(map_coordinates)
+                .iter_shared(),
+        );
+        let point = to_vec2(local_collision);
+        // The closest edge may be on the outline or on one of its holes.
+        let (distance, outline, hole) = cells
+            .iter()
+            .filter_map(|cell| self.outlines_by_cell.get(cell))
+            .flatten()
+            .map(|index| &self.outlines[*index])
+            .flat_map(|outline| {
+                let holes = outline
+                    .holes
+                    .iter()
+                    .map(move |hole| (distance_to_edges(point, hole), outline, Some(hole)));
+                std::iter::once((distance_to_edges(point, &outline.points), outline, None))
+                    .chain(holes)
+            })
+            .min_by(|(d1, ..), (d2, ..)| d1.total_cmp(d2))?;
+        if distance > MAX_DISTANCE_FROM_EDGE {
+            return None;
+        }
+        let points = match hole {
+            Some(hole) => join_hole(&outline.points, hole),
+            None => outline.points.clone(),
+        };
+        Some(
+            points
+                .iter()
+                .map(|point| to_vec2(tile_map_layer.to_global(to_vector2(*point))))
+                .collect(),
+        )
+    }
+}
+
+// Collision polygons (in the layer's local coordinates) of the tile at `cell`,
+// from every physics layer of the tile set that `collision_mask` detects.
+// Polygon points are relative to the tile's center, as given by `map_to_local`,
+// whatever the shape of the tiles.
+pub fn tile_polygons(
+    tile_map_layer: &Gd<TileMapLayer>,
+    cell: Vector2i,
+    collision_mask: u32,
+) -> Vec<PackedVector2Array> {
+    let mut polygons = Vec::new();
+    let (Some(tile_set), Some(tile_data)) = (
+        tile_map_layer.get_tile_set(),
+        tile_map_layer.get_cell_tile_data(cell),
+    ) else {
+        return polygons;
+    };
+    let tile_center = tile_map_layer.map_to_local(cell);
+    for layer_id in 0..tile_set.get_physics_layers_count() {
+        if tile_set.get_physics_layer_collision_layer(layer_id) & collision_mask == 0 {
+            continue;
+        }
+        for polygon_index in 0..tile_data.get_collision_polygons_count(layer_id) {
+            let mut points = tile_data.get_collision_polygon_points(layer_id, polygon_index);
+            for point in points.as_mut_slice() {
+                *point += tile_center;
+            }
+            polygons.push(points);
+        }
+    }
+    polygons
+}
+
+// Polygons that were merged into one outline by `merge_touching_polygons`.
+pub struct MergedPolygon {
+    pub points: PackedVector2Array,
+    // Gaps enclosed by `points`.
+    pub holes: Vec<PackedVector2Array>,
+    // Indices of the polygons that were merged.
+    pub sources: Vec<usize>,
+    // Bounding rect of `points`.
+    bounds: Rect2,
+}
+
+// Merge each polygon with all of those that it overlaps or touches, e.g. the
+// polygons of adjacent tiles that share an edge.
+pub fn merge_touching_polygons(polygons: Vec<PackedVector2Array>) -> Vec<MergedPolygon> {
+    let mut geometry = Geometry2D::singleton();
+    let mut merged: Vec<MergedPolygon> = Vec::new();
+    for (index, points) in polygons.into_iter().enumerate() {
+        let mut current = MergedPolygon {
+            bounds: bounds(&points),
+            points,
+            holes: Vec::new(),
+            sources: vec![index],
+        };
+        // Merge with every outline that this polygon touches. Those outlines
+        // may themselves now be connected.
+        let mut i = 0;
+        while i < merged.len() {
+            // Polygons whose bounds don't touch can't touch either, and
+            // skipping them avoids merging every pair of polygons in a level.
+            if !merged[i]
+                .bounds
+                .grow(TOUCH_MARGIN)
+                .intersects(current.bounds)
+            {
+                i += 1;
+                continue;
+            }
+            let result = geometry.merge_polygons(&merged[i].points, &current.points);
+            let (outer, holes): (Vec<_>, Vec<_>) = result
+                .iter_shared()
+                .partition(|polygon| !geometry.is_polygon_clockwise(polygon));
+            if outer.len() == 1 {
+                let other = merged.swap_remove(i);
+                // Existing holes shrink where the other polygon fills them.
+                let mut all_holes = Vec::new();
+                for hole in &other.holes {
+                    all_holes.extend(clip_hole(&mut geometry, hole, &current.points));
+                }
+                for hole in &current.holes {
+                    all_holes.extend(clip_hole(&mut geometry, hole, &other.points));
+                }
+                all_holes.extend(holes);
+                current.points = outer.into_iter().next().unwrap();
+                current.bounds = bounds(&current.points);
+                current.holes = all_holes;
+                current.sources.extend(other.sources);
+                // Check the outline swapped into `i`.
+                continue;
+            }
+            i += 1;
+        }
+        merged.push(current);
+    }
+    merged
+}
+
+// What remains of `hole` once `polygon` is added. Islands inside the hole are
+// dropped, so collisions with them fall back to merging tiles at collision
+// time.
+fn clip_hole(
+    geometry: &mut Gd<Geometry2D>,
+    hole: &PackedVector2Array,
+    polygon: &PackedVector2Array,
+) -> Vec<PackedVector2Array> {
+    geometry
+        .clip_polygons(hole, polygon)
+        .iter_shared()
+        .filter(|remaining| !geometry.is_polygon_clockwise(remaining))
+        .collect()
+}
+
+// The smallest rect containing all of `points`.
+fn bounds(points: &PackedVector2Array) -> Rect2 {
+    let mut points = points.as_slice().iter();
+    let Some(first) = points.next() else {
+        return Rect2::default();
+    };
+    points.fold(Rect2::new(*first, Vector2::ZERO), |rect, point| {
+        rect.expand(*point)
+    })
+}
+
+// Return the global outline of `shape_owner`, the `CollisionPolygon2D` or
+// `CollisionShape2D` of a body such as a `StaticBody2D`. Curved shapes are
+// approximated with straight edges. Note that the player only detects bodies on
+// the "world" physics layer.
+pub fn shape_outline(shape_owner: Gd<Object>) -> Option<PackedVector2Array> {
+    let (local_points, transform) = match shape_owner.try_cast::<CollisionPolygon2D>() {
+        Ok(collision_polygon) => (
+            collision_polygon.get_polygon(),
+            collision_polygon.get_global_transform(),
+        ),
+        Err(shape_owner) => {
+            let collision_shape = shape_owner.try_cast::<CollisionShape2D>().ok()?;
+            let shape = collision_shape.get_shape()?;
+            let points = if let Ok(rectangle) = shape.clone().try_cast::<RectangleShape2D>() {
+                let half_size = rectangle.get_size() / 2.0;
+                [
+                    Vector2::new(-half_size.x, -half_size.y),
+                    Vector2::new(half_size.x, -half_size.y),
+                    Vector2::new(half_size.x, half_size.y),
+                    Vector2::new(-half_size.x, half_size.y),
+                ]
+                .into_iter()
+                .collect()
+            } else if let Ok(circle) = shape.clone().try_cast::<CircleShape2D>() {
+                arc(Vector2::ZERO, circle.get_radius(), 0.0, TAU)
+                    .into_iter()
+                    .collect()
+            } else if let Ok(capsule) = shape.clone().try_cast::<CapsuleShape2D>() {
+                // Vertical, with `height` including both of the rounded ends.
+                let radius = capsule.get_radius();
+                let half_straight = (capsule.get_height() / 2.0 - radius).max(0.0);
+                let mut points = arc(Vector2::new(0.0, -half_straight), radius, PI, TAU);
+                points.extend(arc(Vector2::new(0.0, half_straight), radius, 0.0, PI));
+                points.into_iter().collect()
+            } else if let Ok(convex) = shape.clone().try_cast::<ConvexPolygonShape2D>() {
+                convex.get_points()
+            } else {
+                report_error!("Can't land on a {}", shape.get_class());
+                return None;
+            };
+            (points, collision_shape.get_global_transform())
+        }
+    };
+    Some(
+        local_points
+            .as_slice()
+            .iter()
+            .map(|point| transform * *point)
+            .collect(),
+    )
+}
+
+// Points from `start_angle` to `end_angle` (inclusive) along a circle.
+fn arc(center: Vector2, radius: f32, start_angle: f32, end_angle: f32) -> Vec<Vector2> {
+    let sweep = end_angle - start_angle;
+    let edges = ((sweep * radius / CURVE_EDGE_LENGTH).floor() as usize).max(3);
+    // A full circle would repeat its first point.
+    let points = if sweep >= TAU { edges } else { edges + 1 };
+    (0..points)
+        .map(|i| {
+            let angle = start_angle + sweep * i as f32 / edges as f32;
+            center + Vector2::from_angle(angle) * radius
+        })
+        .collect()
+}
@@ -0,0 +1,21 @@
+use geometry::Vec2;
+use godot::prelude::*;
+
+// Conversions between Godot types and those of the Godot-independent
+// `geometry` crate.
+
+pub fn to_vec2(vector: Vector2) -> Vec2 {
+    Vec2::new(vector.x, vector.y)
+}
+
+pub fn to_vector2(vector: Vec2) -> Vector2 {
+    Vector2::new(vector.x, vector.y)
+}
+
+pub fn to_polygon(points: &PackedVector2Array) -> Vec<Vec2> {
+    points
+        .as_slice()
+        .iter()
+        .map(|point| to_vec2(*point))
+        .collect()
+}
@@ -109,6 +109,16 @@ impl Fly {
     #[func]
     fn on_body_entered(&mut self, _body: Gd<Node2D>) {
         // This `Area2D` only detects the player.
+        self.eat();
+    }
+
+    // Also called when caught by the player's `Tongue`.
+    #[func]
+    pub fn eat(&mut self) {
+        // The player may touch a fly that their tongue just caught.
+        if self.base().is_queued_for_deletion() {
+            return;
+        }
         self.base_mut().queue_free();
         self.base_mut().emit_signal("eaten", &[]);
     }
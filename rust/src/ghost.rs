@@ -0,0 +1,198 @@
+use crate::report_error;
+use godot::classes::file_access::ModeFlags;
+use godot::classes::{AnimatedSprite2D, DirAccess, FileAccess, INode2D, Node2D};
+use godot::prelude::*;
+
+const GHOST_DIRECTORY: &str = "user://ghosts";
+
+// A snapshot of the player for a single physics frame.
+#[derive(Clone)]
+struct GhostFrame {
+    // Global position.
+    position: Vector2,
+    rotation: f32,
+    flip_h: bool,
+    animation: StringName,
+    frame: i32,
+    // False when there is no player to record, e.g. after being eaten and
+    // before respawning.
+    visible: bool,
+}
+
+/// The player's motion through a level, with one entry per physics frame.
+#[derive(Default)]
+pub struct GhostRecording {
+    frames: Vec<GhostFrame>,
+}
+
+impl GhostRecording {
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    // Record the current state of `player`. When there is no player, record a
+    // hidden frame so that the replay stays in sync with the original attempt.
+    pub fn record(&mut self, player: Option<Gd<Node2D>>) {
+        let sprite = player
+            .as_ref()
+            .and_then(|player| player.try_get_node_as::<AnimatedSprite2D>("AnimatedSprite2D"));
+        let frame = match (player, sprite) {
+            (Some(player), Some(sprite)) => GhostFrame {
+                position: player.get_global_position(),
+                rotation: player.get_global_rotation(),
+                flip_h: sprite.is_flipped_h(),
+                animation: sprite.get_animation(),
+                frame: sprite.get_frame(),
+                visible: true,
+            },
+            _ => match self.frames.last() {
+                Some(last) => GhostFrame {
+                    visible: false,
+                    ..last.clone()
+                },
+                None => return,
+            },
+        };
+        self.frames.push(frame);
+    }
+
+    // Save to `user://`, keyed by the level's scene path. Stored as parallel
+    // arrays, which `FileAccess::store_var` handles compactly.
+    pub fn save(&self, level_path: &GString) {
+        DirAccess::make_dir_recursive_absolute(GHOST_DIRECTORY);
+        let path = ghost_path(level_path);
+        let mut file = match FileAccess::open(&path, ModeFlags::WRITE) {
+            Some(file) => file,
+            None => {
+                report_error!("Failed to save ghost to {path}");
+                return;
+            }
+        };
+
+        let frames = &self.frames;
+        let positions: PackedVector2Array = frames.iter().map(|f| f.position).collect();
+        let rotations: PackedFloat32Array = frames.iter().map(|f| f.rotation).collect();
+        let flip_hs: PackedByteArray = frames.iter().map(|f| f.flip_h as u8).collect();
+        let animations: PackedStringArray =
+            frames.iter().map(|f| GString::from(&f.animation)).collect();
+        let sprite_frames: PackedInt32Array = frames.iter().map(|f| f.frame).collect();
+        let visibles: PackedByteArray = frames.iter().map(|f| f.visible as u8).collect();
+
+        let mut dictionary = Dictionary::new();
+        dictionary.set("positions", positions);
+        dictionary.set("rotations", rotations);
+        dictionary.set("flip_hs", flip_hs);
+        dictionary.set("animations", animations);
+        dictionary.set("frames", sprite_frames);
+        dictionary.set("visibles", visibles);
+        file.store_var(&dictionary.to_variant());
+    }
+
+    pub fn load(level_path: &GString) -> Option<GhostRecording> {
+        let path = ghost_path(level_path);
+        if !FileAccess::file_exists(&path) {
+            return None;
+        }
+        let mut file = FileAccess::open(&path, ModeFlags::READ)?;
+        let dictionary = file.get_var().try_to::<Dictionary>().ok()?;
+        let get = |key: &str| dictionary.get(key);
+        let positions = get("positions")?.try_to::<PackedVector2Array>().ok()?;
+        let rotations = get("rotations")?.try_to::<PackedFloat32Array>().ok()?;
+        let flip_hs = get("flip_hs")?.try_to::<PackedByteArray>().ok()?;
+        let animations = get("animations")?.try_to::<PackedStringArray>().ok()?;
+        let sprite_frames = get("frames")?.try_to::<PackedInt32Array>().ok()?;
+        let visibles = get("visibles")?.try_to::<PackedByteArray>().ok()?;
+
+        let len = positions.len();
+        if [
+            rotations.len(),
+            flip_hs.len(),
+            animations.len(),
+            sprite_frames.len(),
+            visibles.len(),
+        ]
+        .iter()
+        .any(|other_len| *other_len != len)
+        {
+            report_error!("Ghost at {path} is corrupt!");
+            return None;
+        }
+
+        let frames = (0..len)
+            .map(|i| GhostFrame {
+                position: positions[i],
+                rotation: rotations[i],
+                flip_h: flip_hs[i] != 0,
+                animation: StringName::from(&animations[i]),
+                frame: sprite_frames[i],
+                visible: visibles[i] != 0,
+            })
+            .collect();
+        Some(GhostRecording { frames })
+    }
+}
+
+// e.g. "res://levels/level.tscn" -> "user://ghosts/levels_level.ghost"
+fn ghost_path(level_path: &GString) -> GString {
+    let name = level_path
+        .to_string()
+        .trim_start_matches("res://")
+        .trim_end_matches(".tscn")
+        .replace('/', "_");
+    GString::from(format!("{GHOST_DIRECTORY}/{name}.ghost"))
+}
+
+/// Translucent frog that replays a `GhostRecording`. It has no physics body,
+/// so it cannot collide with the level or be noticed by flies or predators.
+#[derive(GodotClass)]
+#[class(base=Node2D)]
+pub struct Ghost {
+    recording: GhostRecording,
+    frame_index: usize,
+    sprite: Option<Gd<AnimatedSprite2D>>,
+    base: Base<Node2D>,
+}
+
+#[godot_api]
+impl INode2D for Ghost {
+    fn init(base: Base<Node2D>) -> Self {
+        Self {
+            recording: GhostRecording::default(),
+            frame_index: 0,
+            sprite: None,
+            base,
+        }
+    }
+
+    fn physics_process(&mut self, _delta: f64) {
+        // Once the recording is finished, stay on the final frame.
+        if let Some(frame) = self.recording.frames.get(self.frame_index).cloned() {
+            self.frame_index += 1;
+            self.base_mut().set_global_position(frame.position);
+            self.base_mut().set_global_rotation(frame.rotation);
+            self.base_mut().set_visible(frame.visible);
+            if let Some(sprite) = self.sprite.as_mut() {
+                sprite.set_flip_h(frame.flip_h);
+                if sprite.get_animation() != frame.animation {
+                    sprite.set_animation(&frame.animation);
+                }
+                sprite.set_frame(frame.frame);
+            }
+        }
+    }
+}
+
+impl Ghost {
+    // Replay `recording`, drawn using `sprite`, which should be a copy of the
+    // player's sprite (so that it has the same frames, offset and scale).
+    pub fn start(&mut self, recording: GhostRecording, mut sprite: Gd<AnimatedSprite2D>) {
+        // The recording drives the animation frame by frame.
+        sprite.stop();
+        self.base_mut().add_child(&sprite);
+        self.base_mut().set_modulate(Color::from_rgba(1.0, 1.0, 1.0, 0.4));
+        self.base_mut().hide();
+        self.recording = recording;
+        self.frame_index = 0;
+        self.sprite = Some(sprite);
+    }
+}
@@ -0,0 +1,73 @@
+use crate::player::Player;
+use godot::classes::{Area2D, Engine, IArea2D};
+use godot::prelude::*;
+
+/// Changes the direction of gravity for the player while they are inside it,
+/// e.g. `(0, -1)` to fall upwards. Jumps are relative to gravity, so the
+/// player can walk the ceiling as they would the floor. Where zones overlap,
+/// the one entered most recently applies.
+#[derive(GodotClass)]
+#[class(base=Area2D)]
+pub struct GravityZone {
+    /// Normalized when used. The player's `fall_acceleration` still determines
+    /// the strength.
+    #[export]
+    gravity_direction: Vector2,
+    /// Rotate the camera so that gravity points down the screen.
+    #[export]
+    rotate_camera: bool,
+    base: Base<Area2D>,
+}
+
+#[godot_api]
+impl IArea2D for GravityZone {
+    fn init(base: Base<Area2D>) -> Self {
+        Self {
+            gravity_direction: Vector2::UP,
+            rotate_camera: false,
+            base,
+        }
+    }
+
+    fn ready(&mut self) {
+        if Engine::singleton().is_editor_hint() {
+            return;
+        }
+        let gd = Gd::from_instance_id(self.base().instance_id());
+        self.base_mut()
+            .signals()
+            .body_entered()
+            .connect_obj(&gd, Self::on_body_entered);
+        self.base_mut()
+            .signals()
+            .body_exited()
+            .connect_obj(&gd, Self::on_body_exited);
+    }
+}
+
+#[godot_api]
+impl GravityZone {
+    pub fn gravity(&self) -> Vector2 {
+        self.gravity_direction
+            .try_normalized()
+            .unwrap_or(Vector2::DOWN)
+    }
+
+    pub fn rotates_camera(&self) -> bool {
+        self.rotate_camera
+    }
+
+    #[func]
+    fn on_body_entered(&mut self, body: Gd<Node2D>) {
+        if let Ok(mut player) = body.try_cast::<Player>() {
+            player.bind_mut().enter_gravity_zone(self.to_gd());
+        }
+    }
+
+    #[func]
+    fn on_body_exited(&mut self, body: Gd<Node2D>) {
+        if let Ok(mut player) = body.try_cast::<Player>() {
+            player.bind_mut().exit_gravity_zone(&self.to_gd());
+        }
+    }
+}
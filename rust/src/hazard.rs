@@ -0,0 +1,64 @@
+use crate::player::Player;
+use godot::classes::{Area2D, Engine, IArea2D};
+use godot::prelude::*;
+
+/// Why the player died, other than being eaten. Determines the death
+/// animation.
+#[derive(PartialEq, GodotConvert, Var, Export, Clone, Copy, Debug)]
+#[godot(via=GString)]
+pub enum DeathCause {
+    /// Landed on a hazardous surface, such as thorns.
+    Hazard,
+    /// Fell into water.
+    Drowned,
+    /// Fell below the bottom of the level.
+    Fell,
+}
+
+impl Default for DeathCause {
+    fn default() -> Self {
+        Self::Drowned
+    }
+}
+
+/// An area that kills the player on contact, such as water. For hazardous
+/// surfaces that the player lands on, use the "Hazard" surface material
+/// instead.
+#[derive(GodotClass)]
+#[class(base=Area2D, tool)]
+struct Hazard {
+    #[export]
+    cause: DeathCause,
+    base: Base<Area2D>,
+}
+
+#[godot_api]
+impl IArea2D for Hazard {
+    fn init(base: Base<Area2D>) -> Self {
+        Self {
+            cause: DeathCause::default(),
+            base,
+        }
+    }
+
+    fn ready(&mut self) {
+        if Engine::singleton().is_editor_hint() {
+            return;
+        }
+        let gd = Gd::from_instance_id(self.base().instance_id());
+        self.base_mut()
+            .signals()
+            .body_entered()
+            .connect_obj(&gd, Self::on_body_entered);
+    }
+}
+
+#[godot_api]
+impl Hazard {
+    #[func]
+    fn on_body_entered(&mut self, body: Gd<Node2D>) {
+        if let Ok(mut player) = body.try_cast::<Player>() {
+            player.bind_mut().die(self.cause);
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use godot::classes::control::{GrowDirection, LayoutPreset};
+use godot::classes::{CanvasLayer, ICanvasLayer, Label, VBoxContainer};
+use godot::global::HorizontalAlignment;
+use godot::prelude::*;
+
+const MARGIN: i32 = 16;
+
+/// Heads-up display for a level, showing the player's lives (if limited),
+/// health (in health mode) and active power-ups.
+#[derive(GodotClass)]
+#[class(base=CanvasLayer)]
+pub struct Hud {
+    base: Base<CanvasLayer>,
+}
+
+#[godot_api]
+impl ICanvasLayer for Hud {
+    fn init(base: Base<CanvasLayer>) -> Self {
+        Self { base }
+    }
+
+    fn ready(&mut self) {
+        let mut status = VBoxContainer::new_alloc();
+        status.set_name("Status");
+        status
+            .set_anchors_and_offsets_preset_ex(LayoutPreset::TOP_RIGHT)
+            .margin(MARGIN)
+            .done();
+        status.set_h_grow_direction(GrowDirection::BEGIN);
+        for name in ["Lives", "Health", "Effects"] {
+            let mut label = Label::new_alloc();
+            label.set_name(name);
+            label.set_horizontal_alignment(HorizontalAlignment::RIGHT);
+            status.add_child(&label);
+        }
+        self.base_mut().add_child(&status);
+        self.set_lives(None);
+        self.set_health(0, 0);
+    }
+}
+
+#[godot_api]
+impl Hud {
+    // Hidden when lives are unlimited.
+    pub fn set_lives(&mut self, lives: Option<u32>) {
+        let mut label = self.label("Lives");
+        label.set_visible(lives.is_some());
+        label.set_text(&format!("Lives {}", lives.unwrap_or_default()));
+    }
+
+    // Hidden when `max_health` is 0, outside of health mode.
+    pub fn set_health(&mut self, health: u32, max_health: u32) {
+        let mut label = self.label("Health");
+        label.set_visible(max_health > 0);
+        let hearts =
+            "♥".repeat(health as usize) + &"♡".repeat(max_health.saturating_sub(health) as usize);
+        label.set_text(&format!("Health {hearts}"));
+    }
+
+    pub fn set_effects(&mut self, effects: &PackedStringArray) {
+        let text = effects
+            .as_slice()
+            .iter()
+            .map(GString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.label("Effects").set_text(&text);
+    }
+
+    fn label(&self, name: &str) -> Gd<Label> {
+        self.base().get_node_as::<Label>(&format!("Status/{name}"))
+    }
+}
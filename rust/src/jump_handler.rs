@@ -2,6 +2,9 @@ use crate::jump_meter::JumpMeter;
 use godot::classes::Input;
 use godot::prelude::*;
 
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
 pub trait JumpDetector {
     fn is_jump_pressed(&mut self) -> bool;
 
@@ -41,6 +44,36 @@ impl JumpKeyDetector {
     }
 }
 
+/// `JumpDetector` that allows specifying whether the jump button is pressed in
+/// a script.
+pub struct ScriptedJumpDetector {
+    pressed: Arc<AtomicBool>,
+}
+
+impl ScriptedJumpDetector {
+    pub fn new(atomic_bool: &Arc<AtomicBool>) -> Self {
+        ScriptedJumpDetector {
+            pressed: atomic_bool.clone(),
+        }
+    }
+}
+
+impl JumpDetector for ScriptedJumpDetector {
+    fn is_jump_pressed(&mut self) -> bool {
+        self.pressed.load(Ordering::SeqCst)
+    }
+}
+
+// A jump released in mid-air, to be made on landing.
+struct BufferedJump {
+    strength: f32,
+    // How long jump was held, which may be longer than the part that counts
+    // towards `strength`.
+    press_ms: f32,
+    // How long ago jump was released.
+    age_ms: f32,
+}
+
 /// This struct handles converting input into whether to jump and the jump's
 /// strength, if so.
 #[derive(GodotClass)]
@@ -49,10 +82,26 @@ pub struct JumpHandler {
     /// If `None`, jump is not pressed.
     /// If `Some`, how long jump has been held.
     length_of_jump_press_ms: Option<f32>,
+    // How long jump was held for the most recent jump, which tells a tap
+    // apart from a weak jump even when `max_jump_strength_for_testing` limits
+    // the strength.
+    last_press_ms: f32,
 
     /// How long it takes to max out the jump meter.
     #[export]
     max_time_ms: f32,
+    /// How long before landing jump can be pressed, or released, and still
+    /// count. Charging in mid-air is limited to this, and continues once
+    /// landed. Releasing in mid-air jumps as soon as the player lands, if that
+    /// is within this time.
+    #[export]
+    buffer_window_ms: f32,
+    // Whether the current press started in mid-air.
+    buffering: bool,
+    // How long jump has been held while buffering, as
+    // `length_of_jump_press_ms` stops at `buffer_window_ms`.
+    buffered_press_ms: f32,
+    buffered_jump: Option<BufferedJump>,
     /// Limit the jump strength to a ratio out of 1. If set to 0, the player
     /// cannot jump. At 0.5, the player's jump will max out at half strength.
     /// Can be useful to ensure hitting a particular strength for testing.
@@ -68,7 +117,12 @@ impl INode for JumpHandler {
     fn init(base: Base<Node>) -> Self {
         Self {
             length_of_jump_press_ms: None,
+            last_press_ms: 0.0,
             max_time_ms: 400.0,
+            buffer_window_ms: 150.0,
+            buffering: false,
+            buffered_press_ms: 0.0,
+            buffered_jump: None,
             max_jump_strength_for_testing: 1.0,
             jump_detector: Box::new(JumpKeyDetector::new()),
             disabled: false,
@@ -94,6 +148,15 @@ impl JumpHandler {
             self.jump_meter().hide();
             return None;
         }
+        if let Some(buffered_jump) = self.buffered_jump.take() {
+            self.last_press_ms = buffered_jump.press_ms;
+            return Some(buffered_jump.strength);
+        }
+        if self.buffering {
+            // Landed while charging.
+            self.buffering = false;
+            self.jump_meter().bind_mut().set_buffered(false);
+        }
         if self.length_of_jump_press_ms.is_none() {
             if self.jump_detector.is_jump_pressed() {
                 self.length_of_jump_press_ms = Some(0.0);
@@ -119,15 +182,96 @@ impl JumpHandler {
         }
         // Released jump.
         godot_print!("Jump strength: {strength}");
-        self.length_of_jump_press_ms = None;
+        self.last_press_ms = self.length_of_jump_press_ms.take().unwrap();
         self.jump_meter().hide();
         Some(strength)
     }
 
+    /// Like `handle_input`, but while the player is in mid-air, so the jump is
+    /// saved for landing rather than returned.
+    pub fn buffer_input(&mut self, delta: f64) {
+        if self.disabled {
+            self.jump_meter().hide();
+            return;
+        }
+        let delta_ms = (delta * 1000.0) as f32;
+        if let Some(buffered_jump) = &mut self.buffered_jump {
+            buffered_jump.age_ms += delta_ms;
+            if buffered_jump.age_ms > self.buffer_window_ms {
+                self.buffered_jump = None;
+            }
+        }
+        let Some(duration_ms) = self.length_of_jump_press_ms else {
+            if self.jump_detector.is_jump_pressed() {
+                self.length_of_jump_press_ms = Some(0.0);
+                self.buffering = true;
+                self.buffered_press_ms = 0.0;
+                self.buffered_jump = None;
+                let mut jump_meter = self.jump_meter();
+                jump_meter.bind_mut().set_ratio(0.0);
+                jump_meter.bind_mut().set_buffered(true);
+                jump_meter.show();
+            }
+            return;
+        };
+        self.buffered_press_ms += delta_ms;
+        // Only the end of a long press counts.
+        let duration_ms = (duration_ms + delta_ms).min(self.buffer_window_ms);
+        self.length_of_jump_press_ms = Some(duration_ms);
+        let strength = (duration_ms / self.max_time_ms).min(self.max_jump_strength_for_testing);
+        if self.jump_detector.is_jump_pressed() {
+            self.jump_meter().bind_mut().set_ratio(strength);
+            return;
+        }
+        // Released before landing.
+        self.length_of_jump_press_ms = None;
+        self.buffering = false;
+        self.buffered_jump = Some(BufferedJump {
+            strength,
+            press_ms: self.buffered_press_ms,
+            age_ms: 0.0,
+        });
+        let mut jump_meter = self.jump_meter();
+        jump_meter.bind_mut().set_buffered(false);
+        jump_meter.hide();
+    }
+
+    /// Like `handle_input`, but while the player swings from their tongue.
+    /// Returns `true` when jump is released, to let go.
+    pub fn swing_input(&mut self) -> bool {
+        if self.disabled {
+            return false;
+        }
+        if self.buffering || self.buffered_jump.is_some() {
+            // A jump charged before grappling only lets go.
+            self.buffering = false;
+            self.buffered_jump = None;
+            let mut jump_meter = self.jump_meter();
+            jump_meter.bind_mut().set_buffered(false);
+            jump_meter.hide();
+        }
+        let jump_pressed = self.jump_detector.is_jump_pressed();
+        let released = self.length_of_jump_press_ms.is_some() && !jump_pressed;
+        self.length_of_jump_press_ms = jump_pressed.then_some(0.0);
+        released
+    }
+
     fn jump_meter(&self) -> Gd<JumpMeter> {
         self.base().get_node_as::<JumpMeter>("../JumpMeter")
     }
 
+    pub fn max_time_ms(&self) -> f32 {
+        self.max_time_ms
+    }
+
+    pub fn last_press_ms(&self) -> f32 {
+        self.last_press_ms
+    }
+
+    pub fn set_max_jump_strength_for_testing(&mut self, max_jump_strength: f32) {
+        self.max_jump_strength_for_testing = max_jump_strength;
+    }
+
     pub fn replace_jump_detector(&mut self, detector: Box<dyn JumpDetector>) {
         self.jump_detector = detector;
     }
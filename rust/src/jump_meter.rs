@@ -23,6 +23,9 @@ pub struct JumpMeter {
     inner_style_box: Gd<StyleBoxFlat>,
     #[export]
     fill_color: Color,
+    /// Fill color while charging a jump in mid-air, before landing.
+    #[export]
+    buffered_fill_color: Color,
     //#[export] FIXME: This field is not exported to the editor, though I have
     // tried to fix it with a few guesses:
     // - move it
@@ -48,6 +51,7 @@ impl IControl for JumpMeter {
             shadow_size: 2,
             inner_style_box: StyleBoxFlat::new_gd(),
             fill_color: Color::GREEN,
+            buffered_fill_color: Color::from_rgba(0.0, 1.0, 0.0, 0.5),
             offset_y: 30,
             base,
         }
@@ -97,4 +101,16 @@ impl JumpMeter {
         self.ratio = ratio;
         self.base_mut().queue_redraw();
     }
+
+    // Whether to show the charge as buffered, i.e. the player is not yet on a
+    // surface.
+    pub fn set_buffered(&mut self, buffered: bool) {
+        let fill_color = if buffered {
+            self.buffered_fill_color
+        } else {
+            self.fill_color
+        };
+        self.inner_style_box.set_bg_color(fill_color);
+        self.base_mut().queue_redraw();
+    }
 }
@@ -0,0 +1,91 @@
+use crate::jump_handler::{JumpDetector, JumpHandler, ScriptedJumpDetector};
+use crate::report_error;
+use godot::prelude::*;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Presses and releases jump according to a script, so that a scene can
+/// reproduce the same jumps without any input. Used by test scenes.
+#[derive(GodotClass)]
+#[class(base=Node)]
+pub struct JumpScript {
+    /// The `JumpHandler` to drive.
+    #[export]
+    jump_handler_path: NodePath,
+    /// One entry per jump. `x` is how long to wait in ms (after the scene
+    /// starts or the previous jump is released) before pressing jump, and `y`
+    /// is how long to hold it in ms.
+    #[export]
+    jumps: PackedVector2Array,
+    /// If less than 1, limit the `JumpHandler`'s jump strength, as with its
+    /// `max_jump_strength_for_testing`. Holding jump for at least its
+    /// `max_time_ms` then results in a jump of exactly this strength.
+    #[export(range = (0.0, 1.0))]
+    max_jump_strength: f32,
+    // Whether the script is pretending that the jump button is pressed.
+    pressed: Arc<AtomicBool>,
+    next_jump: usize,
+    curr_time_ms: f64,
+    next_step_time_ms: f64,
+    base: Base<Node>,
+}
+
+#[godot_api]
+impl INode for JumpScript {
+    fn init(base: Base<Node>) -> Self {
+        Self {
+            jump_handler_path: NodePath::from("../Player/JumpHandler"),
+            jumps: PackedVector2Array::new(),
+            max_jump_strength: 1.0,
+            pressed: Arc::new(AtomicBool::new(false)),
+            next_jump: 0,
+            curr_time_ms: 0.0,
+            next_step_time_ms: 0.0,
+            base,
+        }
+    }
+
+    fn ready(&mut self) {
+        match self
+            .base()
+            .try_get_node_as::<JumpHandler>(&self.jump_handler_path)
+        {
+            Some(mut jump_handler) => {
+                let detector: Box<dyn JumpDetector> =
+                    Box::new(ScriptedJumpDetector::new(&self.pressed));
+                let mut handler = jump_handler.bind_mut();
+                handler.replace_jump_detector(detector);
+                if self.max_jump_strength < 1.0 {
+                    handler.set_max_jump_strength_for_testing(self.max_jump_strength);
+                }
+            }
+            None => report_error!(
+                "JumpScript has no JumpHandler at {}",
+                self.jump_handler_path
+            ),
+        }
+        if let Some(jump) = self.jumps.get(0) {
+            self.next_step_time_ms = jump.x as f64;
+        }
+    }
+
+    fn physics_process(&mut self, delta: f64) {
+        self.curr_time_ms += delta * 1000.0;
+        if self.curr_time_ms < self.next_step_time_ms {
+            return;
+        }
+        if let Some(jump) = self.jumps.get(self.next_jump) {
+            if self.pressed.load(Ordering::SeqCst) {
+                self.pressed.store(false, Ordering::SeqCst);
+                self.next_jump += 1;
+                if let Some(next_jump) = self.jumps.get(self.next_jump) {
+                    self.next_step_time_ms += next_jump.x as f64;
+                }
+            } else {
+                self.pressed.store(true, Ordering::SeqCst);
+                self.next_step_time_ms += jump.y as f64;
+            }
+        }
+    }
+}
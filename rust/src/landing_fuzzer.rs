@@ -0,0 +1,383 @@
+use crate::direction::Direction;
+use crate::jump_handler::{JumpDetector, JumpHandler, ScriptedJumpDetector};
+use crate::log;
+use crate::player::{Player, PlayerInfo};
+use crate::report_error;
+use crate::test_runner::speed_up_engine;
+use godot::classes::file_access::ModeFlags;
+use godot::classes::{DirAccess, FileAccess, Json, RandomNumberGenerator, TileMapLayer};
+use godot::prelude::*;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+const REPORT_DIRECTORY: &str = "user://landing_fuzzer";
+
+// Messages from `Player` that indicate a landing went wrong. Each is reported
+// with the jump that caused it.
+const WATCHED_MESSAGES: [&str; 4] = [
+    "Landed with surprise normal",
+    "Created a new collision!",
+    "Shouldn't still have a collision!",
+    "Don't fit on surface!",
+];
+
+enum FuzzState {
+    // Falling after being spawned, or after jumping.
+    Airborne,
+    // Holding jump for this many more ms.
+    Holding(f64),
+    // Released jump, and waiting for the player to leave the surface.
+    Released,
+}
+
+// The jump (if any) responsible for the messages that follow it.
+struct Attempt {
+    // The player just before pressing jump, or when spawned.
+    player_info: PlayerInfo,
+    jump_hold_ms: Option<f64>,
+}
+
+/// Spawns a `Player` next to random surfaces of `level` and fires random jumps,
+/// reporting every jump that triggers one of the `Player`'s landing errors.
+/// Intended to be run headless, e.g.
+///
+/// `godot --headless --path godot res://test_scenes/landing_fuzzer.tscn`
+///
+/// Reports are written to `user://landing_fuzzer/`, one JSON object per line,
+/// with enough information to reproduce the jump in a test scene. Quits with a
+/// non-zero exit code if anything was reported.
+#[derive(GodotClass)]
+#[class(base=Node)]
+struct LandingFuzzer {
+    /// The level to jump around. Only its tiles are used; other nodes, such as
+    /// the player, flies and predators, are removed.
+    #[export]
+    level: Option<Gd<PackedScene>>,
+    /// Total number of jumps to fire.
+    #[export]
+    jumps: i32,
+    /// Number of jumps before moving the player to a new random surface.
+    #[export]
+    jumps_per_spawn: i32,
+    /// Give up on a jump that has not landed after this long, e.g. if the player
+    /// fell out of the level, and spawn somewhere else.
+    #[export]
+    max_airborne_sec: f32,
+    /// How fast the player is thrown at the surface when spawned.
+    #[export]
+    spawn_speed: f32,
+    /// Run the game this many times faster than real time. Physics still steps
+    /// at the usual rate, so behavior should match normal play.
+    #[export]
+    speed_up: i32,
+    /// Seed for the random spawns and jumps, to reproduce a run. `0` picks a
+    /// random seed.
+    #[export]
+    seed: i64,
+    rng: Gd<RandomNumberGenerator>,
+    active_level: Option<Gd<TileMapLayer>>,
+    player: Option<Gd<Player>>,
+    state: FuzzState,
+    airborne_sec: f64,
+    jumps_fired: i32,
+    jumps_since_spawn: i32,
+    attempt: Option<Attempt>,
+    // Whether the fuzzer is pretending that the jump button is pressed.
+    pressed: Arc<AtomicBool>,
+    // One line of JSON per problem found.
+    reports: Vec<GString>,
+    base: Base<Node>,
+}
+
+#[godot_api]
+impl INode for LandingFuzzer {
+    fn init(base: Base<Node>) -> Self {
+        Self {
+            level: None,
+            jumps: 1000,
+            jumps_per_spawn: 10,
+            max_airborne_sec: 5.0,
+            spawn_speed: 300.0,
+            speed_up: 8,
+            seed: 0,
+            rng: RandomNumberGenerator::new_gd(),
+            active_level: None,
+            player: None,
+            state: FuzzState::Airborne,
+            airborne_sec: 0.0,
+            jumps_fired: 0,
+            jumps_since_spawn: 0,
+            attempt: None,
+            pressed: Arc::new(AtomicBool::new(false)),
+            reports: Vec::new(),
+            base,
+        }
+    }
+
+    fn ready(&mut self) {
+        if self.seed == 0 {
+            self.rng.randomize();
+        } else {
+            self.rng.set_seed(self.seed as u64);
+        }
+        godot_print!("Landing fuzzer seed: {}", self.rng.get_seed());
+        speed_up_engine(self.speed_up);
+        log::set_record_warnings(true);
+
+        let Some(level_scene) = self.level.clone() else {
+            report_error!("LandingFuzzer has no level!");
+            self.abort();
+            return;
+        };
+        let Ok(mut level) = level_scene
+            .instantiate()
+            .expect("Failed to instantiate level")
+            .try_cast::<TileMapLayer>()
+        else {
+            report_error!("LandingFuzzer level should be a TileMapLayer");
+            self.abort();
+            return;
+        };
+        for mut child in level.get_children().iter_shared() {
+            if !child.is_class("TileMapLayer") {
+                level.remove_child(&child);
+                child.queue_free();
+            }
+        }
+        level.set("show_ghost", &false.to_variant());
+        self.base_mut().add_child(&level);
+        self.active_level = Some(level);
+        self.spawn();
+    }
+
+    // Runs before the player's `physics_process`, since the player is a
+    // descendant, so messages are checked one frame after they are recorded.
+    fn physics_process(&mut self, delta: f64) {
+        self.check_messages();
+        let Some(player) = self.player.clone() else {
+            return;
+        };
+        match self.state {
+            FuzzState::Airborne => {
+                self.airborne_sec += delta;
+                let landed = {
+                    let player = player.bind();
+                    player.on_surface() && !player.is_shimmying()
+                };
+                if landed {
+                    self.on_landed();
+                } else if self.airborne_sec > self.max_airborne_sec as f64 {
+                    self.spawn();
+                }
+            }
+            FuzzState::Holding(remaining_ms) => {
+                let remaining_ms = remaining_ms - delta * 1000.0;
+                if remaining_ms <= 0.0 {
+                    self.pressed.store(false, Ordering::SeqCst);
+                    self.state = FuzzState::Released;
+                    self.airborne_sec = 0.0;
+                } else {
+                    self.state = FuzzState::Holding(remaining_ms);
+                }
+            }
+            FuzzState::Released => {
+                self.airborne_sec += delta;
+                if !player.bind().on_surface() {
+                    self.state = FuzzState::Airborne;
+                } else if self.airborne_sec > self.max_airborne_sec as f64 {
+                    self.spawn();
+                }
+            }
+        }
+    }
+}
+
+impl LandingFuzzer {
+    fn on_landed(&mut self) {
+        if self.jumps_fired >= self.jumps {
+            self.finish();
+            return;
+        }
+        if self.jumps_since_spawn >= self.jumps_per_spawn {
+            self.spawn();
+            return;
+        }
+        let Some(player) = self.player.as_ref() else {
+            return;
+        };
+        // Hold a little past the max so that full strength jumps are common.
+        let max_time_ms = player
+            .get_node_as::<JumpHandler>("JumpHandler")
+            .bind()
+            .max_time_ms();
+        let jump_hold_ms = self.rng.randf_range(0.0, max_time_ms * 1.25) as f64;
+        self.attempt = Some(Attempt {
+            player_info: player.bind().get_player_info(),
+            jump_hold_ms: Some(jump_hold_ms),
+        });
+        self.pressed.store(true, Ordering::SeqCst);
+        self.state = FuzzState::Holding(jump_hold_ms);
+        self.jumps_fired += 1;
+        self.jumps_since_spawn += 1;
+    }
+
+    // Replace the player with a new one, thrown at a random surface.
+    fn spawn(&mut self) {
+        if let Some(mut player) = self.player.take() {
+            player.queue_free();
+        }
+        let Some(mut level) = self.active_level.clone() else {
+            return;
+        };
+        let Some(player_info) = self.random_spawn(&level) else {
+            report_error!("Could not find anywhere to spawn the player!");
+            self.finish();
+            return;
+        };
+
+        let scene = load::<PackedScene>("res://player.tscn");
+        let mut player = scene.instantiate().unwrap().cast::<Player>();
+        player.set_name("Player");
+        player.bind_mut().set_player_info(&player_info);
+        let detector: Box<dyn JumpDetector> = Box::new(ScriptedJumpDetector::new(&self.pressed));
+        player
+            .get_node_as::<JumpHandler>("JumpHandler")
+            .bind_mut()
+            .replace_jump_detector(detector);
+        level.add_child(&player);
+
+        self.pressed.store(false, Ordering::SeqCst);
+        self.player = Some(player);
+        self.state = FuzzState::Airborne;
+        self.airborne_sec = 0.0;
+        self.jumps_since_spawn = 0;
+        self.attempt = Some(Attempt {
+            player_info,
+            jump_hold_ms: None,
+        });
+    }
+
+    // Place the player in an empty cell next to a random side of a random
+    // tile, moving towards that side.
+    fn random_spawn(&mut self, level: &Gd<TileMapLayer>) -> Option<PlayerInfo> {
+        let used_cells = level.get_used_cells();
+        if used_cells.is_empty() {
+            return None;
+        }
+        const SIDES: [Vector2i; 4] = [
+            Vector2i::UP,
+            Vector2i::DOWN,
+            Vector2i::LEFT,
+            Vector2i::RIGHT,
+        ];
+        const MAX_TRIES: i32 = 100;
+        for _ in 0..MAX_TRIES {
+            let cell_index = self.rng.randi_range(0, used_cells.len() as i32 - 1);
+            let cell = used_cells.at(cell_index as usize);
+            let side = SIDES[self.rng.randi_range(0, SIDES.len() as i32 - 1) as usize];
+            let spawn_cell = cell - side;
+            if level.get_cell_source_id(spawn_cell) != -1 {
+                continue;
+            }
+            let direction = if self.rng.randf() < 0.5 {
+                Direction::Left
+            } else {
+                Direction::Right
+            };
+            return Some(PlayerInfo::new(
+                level.map_to_local(spawn_cell),
+                side.cast_float() * self.spawn_speed,
+                direction,
+            ));
+        }
+        None
+    }
+
+    fn check_messages(&mut self) {
+        let messages = log::take_recorded_errors()
+            .into_iter()
+            .chain(log::take_recorded_warnings());
+        for message in messages {
+            if !WATCHED_MESSAGES
+                .iter()
+                .any(|watched| message.starts_with(watched))
+            {
+                continue;
+            }
+            let Some(attempt) = self.attempt.as_ref() else {
+                continue;
+            };
+            let level_path = self
+                .level
+                .as_ref()
+                .map_or(GString::new(), |level| level.get_path());
+            let mut report = Dictionary::new();
+            report.set("message", message);
+            report.set("level", level_path);
+            report.set("player", attempt.player_info.to_dictionary());
+            match attempt.jump_hold_ms {
+                Some(jump_hold_ms) => report.set("jump_hold_ms", jump_hold_ms),
+                None => report.set("jump_hold_ms", Variant::nil()),
+            }
+            let line = Json::stringify(&report.to_variant());
+            godot_print!("{line}");
+            self.reports.push(line);
+        }
+    }
+
+    fn finish(&mut self) {
+        // Stop jumping while waiting to quit.
+        self.player = None;
+        log::set_record_warnings(false);
+        let exit_code = if self.reports.is_empty() {
+            godot_print!("No problems found in {} jumps", self.jumps_fired);
+            0
+        } else {
+            self.write_reports();
+            godot_print!(
+                "Found {} problems in {} jumps",
+                self.reports.len(),
+                self.jumps_fired
+            );
+            1
+        };
+        if let Some(mut scene_tree) = self.base().get_tree() {
+            scene_tree.quit_ex().exit_code(exit_code).done();
+        }
+    }
+
+    // Quit without fuzzing, e.g. when misconfigured. Fails the run, so that
+    // a broken setup doesn't look like a clean one.
+    fn abort(&mut self) {
+        log::set_record_warnings(false);
+        if let Some(mut scene_tree) = self.base().get_tree() {
+            scene_tree.quit_ex().exit_code(1).done();
+        }
+    }
+
+    // e.g. "user://landing_fuzzer/levels_level_1234.jsonl" for seed 1234.
+    fn write_reports(&self) {
+        DirAccess::make_dir_recursive_absolute(REPORT_DIRECTORY);
+        let level_name = self.level.as_ref().map_or(String::new(), |level| {
+            level
+                .get_path()
+                .to_string()
+                .trim_start_matches("res://")
+                .trim_end_matches(".tscn")
+                .replace('/', "_")
+        });
+        let path = format!(
+            "{REPORT_DIRECTORY}/{level_name}_{}.jsonl",
+            self.rng.get_seed()
+        );
+        let Some(mut file) = FileAccess::open(&path, ModeFlags::WRITE) else {
+            report_error!("Failed to write landing fuzzer reports to {path}");
+            return;
+        };
+        for report in &self.reports {
+            file.store_line(report);
+        }
+        godot_print!("Wrote reports to {path}");
+    }
+}
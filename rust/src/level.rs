@@ -1,6 +1,14 @@
+use crate::collision_outlines::CollisionOutlines;
+use crate::ghost::{Ghost, GhostRecording};
+use crate::hazard::DeathCause;
+use crate::hud::Hud;
+use crate::lives::Lives;
+use crate::log;
 use crate::player::Player;
 use crate::player::PlayerInfo;
-use godot::classes::{Camera2D, ITileMapLayer, InputEvent, TileMapLayer, Timer};
+use crate::report_error;
+use geometry::Vec2;
+use godot::classes::{AnimatedSprite2D, Camera2D, ITileMapLayer, InputEvent, TileMapLayer, Timer};
 use godot::prelude::*;
 
 #[derive(PartialEq)]
@@ -9,6 +17,7 @@ enum State {
     JumpToRespawn,
     Won,
     BonusFound,
+    GameOver,
 }
 
 /// Code for playing a level.
@@ -17,6 +26,38 @@ enum State {
 pub struct Level {
     player_respawn_info: Option<PlayerInfo>,
     state: State,
+    /// Show a translucent frog replaying the best completion of this level.
+    #[export]
+    show_ghost: bool,
+    // The current attempt, saved if it beats `best_ghost_length`.
+    ghost_recording: GhostRecording,
+    // Number of frames in the best completion, if any.
+    best_ghost_length: Option<usize>,
+    // Built on demand, and cleared whenever the tiles change.
+    collision_outlines: Option<CollisionOutlines>,
+    /// How far beyond the tiles (in any direction, as gravity can change) the
+    /// player can fall before dying.
+    #[export]
+    kill_plane_margin: f32,
+    // The tiles' bounds grown by `kill_plane_margin`, in local coordinates.
+    // `None` when the level has no tiles.
+    kill_bounds: Option<Rect2>,
+    /// Set to true to log what happens to the player, such as how they died.
+    #[export]
+    debug_player: bool,
+    /// Let the player survive hits from predators, with knockback, until they
+    /// run out of health.
+    #[export]
+    health_mode: bool,
+    /// Number of hits the player can take in health mode.
+    #[export]
+    max_health: u32,
+    /// When a campaign limits lives, restart here after a game over, once
+    /// this level has been reached.
+    #[export]
+    checkpoint: bool,
+    // `None` if lives are unlimited.
+    lives: Option<Lives>,
     base: Base<TileMapLayer>,
 }
 
@@ -26,6 +67,17 @@ impl ITileMapLayer for Level {
         Self {
             player_respawn_info: None,
             state: State::Playing,
+            show_ghost: true,
+            ghost_recording: GhostRecording::default(),
+            best_ghost_length: None,
+            collision_outlines: None,
+            kill_plane_margin: 512.0,
+            kill_bounds: None,
+            debug_player: false,
+            health_mode: false,
+            max_health: 3,
+            checkpoint: false,
+            lives: None,
             base,
         }
     }
@@ -38,6 +90,15 @@ impl ITileMapLayer for Level {
             "connect",
             &["player_eaten".to_variant(), on_player_eaten.to_variant()],
         );
+        let on_player_damaged = self.base().callable("on_player_damaged");
+        scene_tree.call_group(
+            "predators",
+            "connect",
+            &[
+                "player_damaged".to_variant(),
+                on_player_damaged.to_variant(),
+            ],
+        );
 
         let on_prey_eaten = self.base().callable("on_prey_eaten");
         scene_tree.call_group(
@@ -53,8 +114,36 @@ impl ITileMapLayer for Level {
             &["eaten".to_variant(), on_bonus_found.to_variant()],
         );
 
-        if let Some(player) = self.player() {
+        if let Some(mut player) = self.player() {
             self.player_respawn_info = Some(player.bind().get_player_info());
+            self.connect_player(&mut player);
+        }
+
+        let used_rect = self.base().get_used_rect();
+        if used_rect.size != Vector2i::ZERO {
+            let top_left = self.base().map_to_local(used_rect.position);
+            let bottom_right = self.base().map_to_local(used_rect.end());
+            self.kill_bounds =
+                Some(Rect2::from_corners(top_left, bottom_right).grow(self.kill_plane_margin));
+        }
+
+        self.spawn_ghost();
+
+        let on_tiles_changed = self.base().callable("on_tiles_changed");
+        self.base_mut().connect("changed", &on_tiles_changed);
+        // Build ahead of time to avoid a hitch on the first landing.
+        if let Some(player) = self.player() {
+            let collision_mask = player.get_collision_mask();
+            let collision_outlines = CollisionOutlines::build(&self.base(), collision_mask, false);
+            self.collision_outlines = Some(collision_outlines);
+        }
+    }
+
+    fn physics_process(&mut self, _delta: f64) {
+        if self.state == State::Playing {
+            let player = self.player().map(|player| player.upcast::<Node2D>());
+            self.ghost_recording.record(player);
+            self.check_kill_plane();
         }
     }
 
@@ -74,10 +163,76 @@ impl Level {
     pub fn complete_level();
     #[signal]
     pub fn find_bonus();
+    #[signal]
+    pub fn lives_changed(remaining: u32, flies: u32);
+    #[signal]
+    pub fn game_over();
+
+    // Limit the player's lives for this level, or not with `None`.
+    pub fn set_lives(&mut self, lives: Option<Lives>) {
+        self.lives = lives;
+        let remaining = lives.map(|lives| lives.remaining);
+        self.hud().bind_mut().set_lives(remaining);
+    }
+
+    pub fn is_checkpoint(&self) -> bool {
+        self.checkpoint
+    }
 
     #[func]
-    fn on_player_eaten(&mut self, mut player: Gd<Node2D>) {
+    fn on_player_eaten(&mut self, player: Gd<Node2D>) {
         godot_print!("on_player_eaten! eating {}", player.get_name());
+        self.remove_player(player);
+    }
+
+    // Only called in health mode. The last hit eats the player.
+    #[func]
+    fn on_player_damaged(&mut self, player: Gd<Node2D>, hit_direction: Vector2) {
+        let Ok(mut player) = player.try_cast::<Player>() else {
+            return;
+        };
+        let survived = player.bind_mut().take_hit(hit_direction);
+        self.update_health(&player);
+        if !survived {
+            self.on_player_eaten(player.upcast());
+        }
+    }
+
+    #[func]
+    fn on_player_died(&mut self, player: Gd<Node2D>) {
+        let cause = player
+            .clone()
+            .try_cast::<Player>()
+            .ok()
+            .and_then(|player| player.bind().death_cause());
+        log!(
+            self.debug_player,
+            "on_player_died! {} died: {cause:?}",
+            player.get_name()
+        );
+        self.remove_player(player);
+    }
+
+    #[func]
+    fn on_player_effects_changed(&mut self, effects: PackedStringArray) {
+        self.hud().bind_mut().set_effects(&effects);
+    }
+
+    fn connect_player(&mut self, player: &mut Gd<Player>) {
+        let max_health = if self.health_mode { self.max_health } else { 0 };
+        player.bind_mut().set_max_health(max_health);
+        self.update_health(player);
+        let on_player_died = self.base().callable("on_player_died");
+        player.connect("died", &on_player_died);
+        let on_player_effects_changed = self.base().callable("on_player_effects_changed");
+        player.connect("effects_changed", &on_player_effects_changed);
+    }
+
+    // Remove a player that was eaten or died, leaving the camera in place. Once
+    // no players remain, wait for the jump to respawn.
+    fn remove_player(&mut self, mut player: Gd<Node2D>) {
+        // Power-ups don't carry over to the next life.
+        self.hud().bind_mut().set_effects(&PackedStringArray::new());
         if let Some(mut parent) = player.get_parent() {
             if let Some(mut camera) = player.try_get_node_as::<Camera2D>("Camera2D") {
                 // Reparent the camera so it can stay in place when the player
@@ -89,17 +244,22 @@ impl Level {
             parent.remove_child(&player);
             player.queue_free();
         } else {
-            godot_error!("player_eaten signal called on node not in tree?");
+            report_error!("Removing player that is not in the tree?");
         }
         if let Some(mut scene_tree) = self.base().get_tree() {
             // Support multiple players with different names.
             let players_remaining = scene_tree.get_nodes_in_group("player").len();
             if players_remaining == 0 && self.state == State::Playing {
-                self.state = State::JumpToRespawn;
-                self.respawn_hint_delay_timer()
-                    .start_ex()
-                    .time_sec(1.0)
-                    .done();
+                if self.lose_life() {
+                    self.state = State::JumpToRespawn;
+                    self.respawn_hint_delay_timer()
+                        .start_ex()
+                        .time_sec(1.0)
+                        .done();
+                } else {
+                    self.state = State::GameOver;
+                    self.signals().game_over().emit();
+                }
             }
         }
     }
@@ -110,9 +270,11 @@ impl Level {
             // When the last prey is eaten, it is queued for removal, but the
             // signal should call this method before the prey is removed.
             let prey_remaining = scene_tree.get_nodes_in_group("prey").len();
+            self.eat_fly();
             if prey_remaining <= 1 {
                 self.state = State::Won;
                 self.disable_jumping();
+                self.save_ghost_if_best();
                 self.signals().complete_level().emit();
             }
         }
@@ -120,11 +282,52 @@ impl Level {
 
     #[func]
     fn on_bonus_found(&mut self) {
+        self.eat_fly();
         self.state = State::BonusFound;
         self.disable_jumping();
         self.signals().find_bonus().emit();
     }
 
+    #[func]
+    fn on_tiles_changed(&mut self) {
+        self.collision_outlines = None;
+    }
+
+    // Return the merged and smoothed outline (in global coordinates) of the
+    // tiles that a body with `collision_mask` collided with at
+    // `collision_position`. Returns `None` if the outline is not cached, in
+    // which case the tiles need to be merged at collision time.
+    pub fn collision_outline(
+        &mut self,
+        collision_position: Vector2,
+        collision_mask: u32,
+        debug_collisions: bool,
+    ) -> Option<Vec<Vec2>> {
+        let tile_map_layer = self.base().clone();
+        if self
+            .collision_outlines
+            .as_ref()
+            .is_some_and(|outlines| outlines.collision_mask() != collision_mask)
+        {
+            self.collision_outlines = None;
+        }
+        let outlines = self.collision_outlines.get_or_insert_with(|| {
+            CollisionOutlines::build(&tile_map_layer, collision_mask, debug_collisions)
+        });
+        outlines.find(&tile_map_layer, collision_position)
+    }
+
+    // Kill a player that fell out of the level, rather than letting them fall
+    // forever.
+    fn check_kill_plane(&mut self) {
+        let (Some(kill_bounds), Some(mut player)) = (self.kill_bounds, self.player()) else {
+            return;
+        };
+        if !kill_bounds.contains_point(player.get_position()) {
+            player.bind_mut().die(DeathCause::Fell);
+        }
+    }
+
     fn player(&self) -> Option<Gd<Player>> {
         self.base().try_get_node_as::<Player>("Player")
     }
@@ -134,12 +337,15 @@ impl Level {
             let scene = load::<PackedScene>("res://player.tscn");
             let mut player = scene.instantiate().unwrap().cast::<Player>();
             player.bind_mut().set_player_info(respawn_info);
+            self.connect_player(&mut player);
 
             // When the player dies, we reparent the camera to the level. Restore it
             // on the new player.
             if let Some(mut camera) = self.base().try_get_node_as::<Camera2D>("Camera2D") {
                 self.base_mut().remove_child(&camera);
                 camera.set_position(Vector2::ZERO);
+                // Undo any rotation from a `GravityZone`.
+                camera.set_rotation(0.0);
                 player.add_child(&camera);
             }
             self.base_mut().add_child(&player);
@@ -177,6 +383,54 @@ impl Level {
         }
     }
 
+    // Returns whether the player has a life left to respawn with.
+    fn lose_life(&mut self) -> bool {
+        let Some(lives) = &mut self.lives else {
+            return true;
+        };
+        let has_lives = lives.lose_life();
+        self.report_lives();
+        has_lives
+    }
+
+    fn eat_fly(&mut self) {
+        let Some(lives) = &mut self.lives else {
+            return;
+        };
+        if lives.eat_fly() {
+            log!(self.debug_player, "Extra life! {} lives", lives.remaining);
+        }
+        self.report_lives();
+    }
+
+    fn report_lives(&mut self) {
+        if let Some(lives) = self.lives {
+            self.hud().bind_mut().set_lives(Some(lives.remaining));
+            self.signals()
+                .lives_changed()
+                .emit(lives.remaining, lives.flies);
+        }
+    }
+
+    fn update_health(&mut self, player: &Gd<Player>) {
+        let (health, max_health) = {
+            let player = player.bind();
+            (player.health(), player.max_health())
+        };
+        self.hud().bind_mut().set_health(health, max_health);
+    }
+
+    fn hud(&mut self) -> Gd<Hud> {
+        let name = "Hud";
+        if let Some(hud) = self.base().try_get_node_as::<Hud>(name) {
+            return hud;
+        }
+        let mut hud = Hud::new_alloc();
+        hud.set_name(name);
+        self.base_mut().add_child(&hud);
+        hud
+    }
+
     fn respawn_hint(&self, create: bool) -> Option<Gd<Node>> {
         let name = "RespawnHint";
         if let Some(hint) = self.base().try_get_node_as::<Node>(name) {
@@ -191,6 +445,44 @@ impl Level {
         Some(hint)
     }
 
+    fn spawn_ghost(&mut self) {
+        let level_path = self.base().get_scene_file_path();
+        if level_path.is_empty() {
+            return;
+        }
+        if let Some(recording) = GhostRecording::load(&level_path) {
+            self.best_ghost_length = Some(recording.len());
+            if !self.show_ghost {
+                return;
+            }
+            // Copy the player's sprite so the ghost matches its frames, offset
+            // and scale.
+            let sprite = self
+                .player()
+                .and_then(|player| player.try_get_node_as::<AnimatedSprite2D>("AnimatedSprite2D"))
+                .and_then(|sprite| sprite.duplicate())
+                .and_then(|sprite| sprite.try_cast::<AnimatedSprite2D>().ok());
+            if let Some(sprite) = sprite {
+                let mut ghost = Ghost::new_alloc();
+                ghost.set_name("Ghost");
+                ghost.bind_mut().start(recording, sprite);
+                self.base_mut().add_child(&ghost);
+            }
+        }
+    }
+
+    fn save_ghost_if_best(&mut self) {
+        let level_path = self.base().get_scene_file_path();
+        if level_path.is_empty() {
+            return;
+        }
+        let length = self.ghost_recording.len();
+        if self.best_ghost_length.is_none_or(|best| length < best) {
+            self.ghost_recording.save(&level_path);
+            self.best_ghost_length = Some(length);
+        }
+    }
+
     fn disable_jumping(&self) {
         let mut scene_tree = self.base().get_tree().unwrap();
         scene_tree.call_group("player", "disable_jumping", &[]);
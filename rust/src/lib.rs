@@ -3,21 +3,38 @@ use godot::prelude::*;
 mod alligator;
 mod arrow;
 mod button_hint;
+mod collision_outlines;
+mod conversions;
 mod direction;
 mod fly;
+mod ghost;
+mod gravity_zone;
+mod hazard;
+mod hud;
 mod jump_handler;
 mod jump_meter;
-mod landing_surface;
+mod jump_script;
+mod landing_fuzzer;
 mod level;
+mod lives;
 mod log;
 mod main_node;
-mod math;
 mod message_screen;
+mod path_platform;
 mod player;
+mod power_up;
+mod repro;
+mod scene_list;
 mod steal_enter;
+mod surface_material;
 mod test_alligator;
+mod test_expectations;
+mod test_runner;
+mod tongue;
+mod tongue_script;
 mod toucan;
 mod tutorial;
+mod wind_zone;
 
 struct RustExtension;
 
@@ -0,0 +1,32 @@
+// Lives remaining, for campaigns that limit them. Tracked by `Main` across
+// levels, and by each `Level` while it is played.
+#[derive(Clone, Copy, Debug)]
+pub struct Lives {
+    pub remaining: u32,
+    // Flies eaten towards the next extra life.
+    pub flies: u32,
+    // 0 for no extra lives.
+    pub flies_per_extra_life: u32,
+}
+
+impl Lives {
+    // Returns whether eating the fly earned an extra life.
+    pub fn eat_fly(&mut self) -> bool {
+        if self.flies_per_extra_life == 0 {
+            return false;
+        }
+        self.flies += 1;
+        if self.flies < self.flies_per_extra_life {
+            return false;
+        }
+        self.flies = 0;
+        self.remaining += 1;
+        true
+    }
+
+    // Returns whether any lives remain.
+    pub fn lose_life(&mut self) -> bool {
+        self.remaining = self.remaining.saturating_sub(1);
+        self.remaining > 0
+    }
+}
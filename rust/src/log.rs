@@ -1,3 +1,6 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
 // Helper for optional log statements that can be toggled with a boolean.
 #[macro_export]
 macro_rules! log {
@@ -7,3 +10,60 @@ macro_rules! log {
         }
     };
 }
+
+// Errors reported with `report_error!`. Automated runs (e.g. the
+// `TestRunner`) check these, since Godot does not let us intercept
+// `godot_error!` itself. So use `report_error!` for every error.
+static RECORDED_ERRORS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+pub fn record_error(message: String) {
+    if let Ok(mut errors) = RECORDED_ERRORS.lock() {
+        errors.push(message);
+    }
+}
+
+// Return the errors recorded since the last call, clearing them.
+pub fn take_recorded_errors() -> Vec<String> {
+    match RECORDED_ERRORS.lock() {
+        Ok(mut errors) => std::mem::take(&mut *errors),
+        Err(_) => Vec::new(),
+    }
+}
+
+// Unexpected but non-fatal cases, e.g. landing on a surface that is too small.
+// Only recorded when enabled (e.g. by the `LandingFuzzer`), since unlike errors
+// they happen during normal play.
+static RECORD_WARNINGS: AtomicBool = AtomicBool::new(false);
+static RECORDED_WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+pub fn set_record_warnings(enabled: bool) {
+    RECORD_WARNINGS.store(enabled, Ordering::SeqCst);
+}
+
+pub fn record_warning(message: String) {
+    if !RECORD_WARNINGS.load(Ordering::SeqCst) {
+        return;
+    }
+    if let Ok(mut warnings) = RECORDED_WARNINGS.lock() {
+        warnings.push(message);
+    }
+}
+
+// Return the warnings recorded since the last call, clearing them.
+pub fn take_recorded_warnings() -> Vec<String> {
+    match RECORDED_WARNINGS.lock() {
+        Ok(mut warnings) => std::mem::take(&mut *warnings),
+        Err(_) => Vec::new(),
+    }
+}
+
+// Like `godot_error!`, but also records the message so that automated runs can
+// detect it.
+#[macro_export]
+macro_rules! report_error {
+    ($fmt:literal $(, $args:expr)* $(,)?) => {{
+        let message = format!($fmt $(, $args)*);
+        godot_error!("{}", message);
+        $crate::log::record_error(message);
+    }};
+}
@@ -1,5 +1,8 @@
 use crate::level::Level;
+use crate::lives::Lives;
 use crate::message_screen::MessageScreen;
+use crate::report_error;
+use crate::scene_list::SceneList;
 use crate::tutorial::Tutorial;
 use godot::classes::{AudioStreamPlayer, InputEvent};
 use godot::prelude::*;
@@ -10,6 +13,9 @@ struct Main {
     /// List of levels and other scenes (e.g. title screen) to play, in order.
     #[export]
     scenes: Array<Gd<PackedScene>>,
+    /// If set, play its scenes instead of `scenes`.
+    #[export]
+    scene_list: Option<Gd<SceneList>>,
     /// Zero-based index of the scene to start on. Useful for testing.
     #[export]
     scene_index: i32,
@@ -20,6 +26,23 @@ struct Main {
     active_scene: Option<Gd<Node>>,
     // Packed version of current scene for reloading.
     active_scene_packed: Option<Gd<PackedScene>>,
+    /// How many lives the player starts the campaign with. 0 for unlimited.
+    #[export]
+    starting_lives: u32,
+    /// How many flies the player must eat to earn an extra life. 0 for none.
+    #[export]
+    flies_per_extra_life: u32,
+    /// After a game over, restart from the last `Level` marked as a
+    /// checkpoint, if any, rather than from the start of the campaign.
+    #[export]
+    restart_from_checkpoint: bool,
+    // `None` if lives are unlimited.
+    lives: Option<Lives>,
+    // Where the campaign started, for restarting after a game over.
+    start_index: i32,
+    checkpoint_index: Option<i32>,
+    // Whether the message screen showing is for a game over.
+    game_over: bool,
     base: Base<Node>,
 }
 
@@ -28,16 +51,29 @@ impl INode for Main {
     fn init(base: Base<Node>) -> Self {
         Self {
             scenes: Array::<Gd<PackedScene>>::new(),
+            scene_list: None,
             scene_index: 0,
             play_bonus_next: false,
             bonus_level: None,
             active_scene: None,
             active_scene_packed: None,
+            starting_lives: 0,
+            flies_per_extra_life: 20,
+            restart_from_checkpoint: true,
+            lives: None,
+            start_index: 0,
+            checkpoint_index: None,
+            game_over: false,
             base,
         }
     }
 
     fn ready(&mut self) {
+        if let Some(scene_list) = &self.scene_list {
+            self.scenes = scene_list.bind().scenes();
+        }
+        self.start_index = self.scene_index;
+        self.lives = self.new_lives();
         self.load_scene();
     }
 
@@ -58,6 +94,14 @@ impl Main {
         if let Some(packed_scene) = self.scenes.get(self.scene_index as usize) {
             godot_print!("Loading scene: {}", self.scene_index);
             self.load_packed_scene(packed_scene);
+            let is_checkpoint = self
+                .active_scene
+                .clone()
+                .and_then(|node| node.try_cast::<Level>().ok())
+                .is_some_and(|level| level.bind().is_checkpoint());
+            if is_checkpoint {
+                self.checkpoint_index = Some(self.scene_index);
+            }
 
             // Play background sounds.
             // FIXME: This should perhaps be generalized/editable from the
@@ -77,7 +121,7 @@ impl Main {
                 }
             }
         } else {
-            godot_error!("Failed to load scene {}", self.scene_index);
+            report_error!("Failed to load scene {}", self.scene_index);
         }
     }
 
@@ -101,6 +145,15 @@ impl Main {
                 .signals()
                 .find_bonus()
                 .connect_obj(&gd, Self::on_bonus_found);
+            level
+                .signals()
+                .lives_changed()
+                .connect_obj(&gd, Self::on_lives_changed);
+            level
+                .signals()
+                .game_over()
+                .connect_obj(&gd, Self::on_game_over);
+            level.bind_mut().set_lives(self.lives);
         } else if let Some(mut message_screen) = node.clone().try_cast::<MessageScreen>().ok() {
             message_screen
                 .signals()
@@ -118,13 +171,17 @@ impl Main {
 
     #[func]
     fn load_next_scene(&mut self) {
+        if self.game_over {
+            self.restart_after_game_over();
+            return;
+        }
         if self.play_bonus_next {
             self.play_bonus_next = false;
             if let Some(bonus_level) = self.bonus_level.clone() {
                 self.load_packed_scene(bonus_level);
                 return;
             }
-            godot_error!("Missing bonus level!");
+            report_error!("Missing bonus level!");
         }
 
         self.scene_index += 1;
@@ -150,6 +207,39 @@ impl Main {
         self.show_message_screen("res://messages/bonus.tscn");
     }
 
+    #[func]
+    fn on_lives_changed(&mut self, remaining: u32, flies: u32) {
+        if let Some(lives) = &mut self.lives {
+            lives.remaining = remaining;
+            lives.flies = flies;
+        }
+    }
+
+    #[func]
+    fn on_game_over(&mut self) {
+        self.game_over = true;
+        self.show_message_screen("res://messages/game_over.tscn");
+    }
+
+    fn restart_after_game_over(&mut self) {
+        self.game_over = false;
+        self.play_bonus_next = false;
+        self.lives = self.new_lives();
+        self.scene_index = match self.checkpoint_index {
+            Some(checkpoint_index) if self.restart_from_checkpoint => checkpoint_index,
+            _ => self.start_index,
+        };
+        self.load_scene();
+    }
+
+    fn new_lives(&self) -> Option<Lives> {
+        (self.starting_lives > 0).then_some(Lives {
+            remaining: self.starting_lives,
+            flies: 0,
+            flies_per_extra_life: self.flies_per_extra_life,
+        })
+    }
+
     fn show_message_screen(&self, scene_name: &str) {
         let packed_scene = load::<PackedScene>(scene_name);
         let mut message_screen = packed_scene.instantiate_as::<MessageScreen>();
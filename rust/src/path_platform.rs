@@ -0,0 +1,49 @@
+use godot::classes::{IPathFollow2D, PathFollow2D};
+use godot::prelude::*;
+
+/// Moves along its parent `Path2D` at a constant speed, carrying any children,
+/// e.g. an `AnimatableBody2D` for the player to ride, such as a lily pad or a
+/// floating log.
+#[derive(GodotClass)]
+#[class(base=PathFollow2D)]
+pub struct PathPlatform {
+    /// In pixels per second.
+    #[export]
+    speed: f32,
+    /// Turn around at each end of the path. Otherwise, `loop` determines
+    /// whether to return to the start.
+    #[export]
+    ping_pong: bool,
+    // 1.0 when moving forward along the path, and -1.0 when moving backward.
+    heading: f32,
+    base: Base<PathFollow2D>,
+}
+
+#[godot_api]
+impl IPathFollow2D for PathPlatform {
+    fn init(base: Base<PathFollow2D>) -> Self {
+        Self {
+            speed: 100.0,
+            ping_pong: true,
+            heading: 1.0,
+            base,
+        }
+    }
+
+    fn ready(&mut self) {
+        if self.ping_pong {
+            self.base_mut().set_loop(false);
+        }
+    }
+
+    fn physics_process(&mut self, delta: f64) {
+        let progress = self.base().get_progress() + self.heading * self.speed * delta as f32;
+        self.base_mut().set_progress(progress);
+        if self.ping_pong {
+            let ratio = self.base().get_progress_ratio();
+            if (self.heading > 0.0 && ratio >= 1.0) || (self.heading < 0.0 && ratio <= 0.0) {
+                self.heading = -self.heading;
+            }
+        }
+    }
+}
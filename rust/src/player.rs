@@ -1,14 +1,24 @@
-use std::collections::HashSet;
 use std::f32::consts::PI;
 
+use crate::collision_outlines::{merge_touching_polygons, shape_outline, tile_polygons};
+use crate::conversions::{to_polygon, to_vec2, to_vector2};
 use crate::direction::Direction;
+use crate::gravity_zone::GravityZone;
+use crate::hazard::DeathCause;
 use crate::jump_handler::JumpHandler;
-use crate::landing_surface::LandingSurface;
+use crate::level::Level;
 use crate::log;
-use crate::math;
+use crate::power_up::{Effect, PowerUpKind};
+use crate::report_error;
+use crate::repro;
+use crate::surface_material::SurfaceMaterial;
+use crate::tongue::{Tongue, PREY_GROUPS};
+use crate::wind_zone::WindZone;
+use geometry::polygon::{distance_to_edges, smooth_polygon};
+use geometry::{AmbiguousNormal, CornerShimmy, Lander, LandingError, LandingSurface, Vec2};
 use godot::classes::{
-    AnimatedSprite2D, Camera2D, CharacterBody2D, CollisionShape2D, Engine, Geometry2D,
-    ICharacterBody2D, KinematicCollision2D, TileMapLayer, Timer,
+    AnimatedSprite2D, Camera2D, CharacterBody2D, CollisionShape2D, Engine, ICharacterBody2D,
+    KinematicCollision2D, TileMapLayer, Timer, Tween,
 };
 use godot::global::{cos, randf, randf_range};
 use godot::prelude::*;
@@ -20,6 +30,44 @@ pub struct PlayerInfo {
     pos: Vector2,
     vel: Vector2,
     dir: Direction,
+    rotation: f32,
+    on_surface: bool,
+    on_ceiling: bool,
+}
+
+impl PlayerInfo {
+    pub fn new(pos: Vector2, vel: Vector2, dir: Direction) -> Self {
+        PlayerInfo {
+            pos,
+            vel,
+            dir,
+            rotation: 0.0,
+            on_surface: false,
+            on_ceiling: false,
+        }
+    }
+
+    // For writing reports, e.g. from the `LandingFuzzer`.
+    pub fn to_dictionary(&self) -> Dictionary {
+        let mut dictionary = Dictionary::new();
+        dictionary.set("position", self.pos);
+        dictionary.set("velocity", self.vel);
+        dictionary.set("direction", self.dir);
+        dictionary.set("rotation", self.rotation);
+        dictionary.set("on_surface", self.on_surface);
+        dictionary.set("on_ceiling", self.on_ceiling);
+        dictionary
+    }
+}
+
+// Sliding along a slippery surface.
+#[derive(Clone, Copy)]
+struct Slide {
+    // Global velocity, parallel to the surface.
+    velocity: Vector2,
+    // The ends of the surface, in global coordinates.
+    a: Vector2,
+    b: Vector2,
 }
 
 // The player's width works well for collisions, but make it a little bit
@@ -27,6 +75,27 @@ pub struct PlayerInfo {
 // player's body but do for their feet.
 const WIDTH_MODIFIER: f32 = 0.7;
 
+// Land on bouncy surfaces instead of rebounding slower than this, rather than
+// bouncing forever with less and less speed.
+const MIN_BOUNCE_SPEED: f32 = 50.0;
+
+// How long it takes the sprite to squash when bouncing, and again to recover.
+const SQUASH_SEC: f64 = 0.08;
+
+// How fast the player drops away from a wall or ceiling when letting go.
+const LET_GO_SPEED: f32 = 20.0;
+
+// How long the death animation for each cause plays before the player is
+// removed.
+const HAZARD_DEATH_SEC: f64 = 0.5;
+const DROWNED_DEATH_SEC: f64 = 1.0;
+// How close flies must be for the `FlyMagnet` power-up to pull them in, and
+// how fast.
+const MAGNET_RANGE: f32 = 192.0;
+const MAGNET_SPEED: f32 = 120.0;
+// How often the sprite flashes while invulnerable, in flashes per second.
+const FLASH_RATE: f32 = 10.0;
+
 #[derive(GodotClass)]
 #[class(base=CharacterBody2D, tool)]
 pub struct Player {
@@ -37,18 +106,101 @@ pub struct Player {
     /// Initial speed when releasing the jump button at full strength.
     #[export]
     max_jump_strength: f32,
+    /// How much jumps follow the surface the player is on, from `0` (jump
+    /// relative to gravity, whatever the slope) to `1` (jump relative to the
+    /// surface's normal, e.g. away from a slanted wall).
+    #[export(range = (0.0, 1.0))]
+    jump_normal_blend: f32,
     #[export]
     fall_acceleration: f32,
     #[export]
     on_surface: bool, // True when on any surface: floor, wall, ceiling.
+    #[export]
     on_ceiling: bool,
+    // True when on a wall, relative to gravity. Only set when landing, so test
+    // scenes that start the player on a wall keep their original jumps.
+    on_wall: bool,
+    /// The angle between a wall and jumps off of it, in degrees. The player
+    /// pushes away from the wall, upwards, whichever way they face.
+    #[export(range = (0.0, 90.0))]
+    wall_jump_angle: f32,
+    /// Releasing jump within this many ms of pressing it lets go of a wall or
+    /// ceiling, dropping off instead of jumping. `0` disables this.
+    #[export]
+    let_go_tap_ms: f32,
     #[export]
     shimmy_speed: f32,
     // If the player lands on a corner, they will "shimmy" until they're fully on
     // the surface
     shimmy_dest: Option<Vector2>,
+    // While shimmying around corners, the tween moving the player, and the
+    // normal of the surface they will end up on.
+    corner_shimmy: Option<(Gd<Tween>, Vector2)>,
+    /// How quickly (in pixels/second^2) friction slows sliding on a slippery
+    /// surface. The player only starts sliding down slopes steep enough for
+    /// gravity to overcome this.
+    #[export]
+    slide_friction: f32,
+    slide: Option<Slide>,
+    /// The fraction of the player's speed kept when rebounding off a bouncy
+    /// surface.
+    #[export]
+    bounce_restitution: f32,
+    // The sprite's scale when not squashed.
+    sprite_scale: Vector2,
+    squash_tween: Option<Gd<Tween>>,
+    // The zones the player is in. Their wind applies while airborne.
+    wind_zones: Vec<Gd<WindZone>>,
+    // The gravity zones the player is in, most recently entered last.
+    gravity_zones: Vec<Gd<GravityZone>>,
+    // The direction of gravity (normalized), updated every frame. Jumps,
+    // landing and "right-side up" are all relative to this.
+    gravity: Vector2,
+    // Set once a gravity zone has rotated the camera, after which the player
+    // keeps the camera's rotation in line with gravity.
+    camera_follows_gravity: bool,
+    // Set when the player dies, after which they stop moving.
+    death_cause: Option<DeathCause>,
+    /// When jumping off a moving platform, add the platform's velocity to the
+    /// jump.
+    #[export]
+    inherit_platform_velocity: bool,
+    // What the player is standing on, along with its global transform as of
+    // the last frame, so the player can move with it.
+    platform: Option<(Gd<Node2D>, Transform2D)>,
+    // How fast the platform carried the player during the last frame.
+    platform_velocity: Vector2,
+    // While swinging from the tongue: the global point it is anchored to, and
+    // the length of the tongue.
+    grapple: Option<(Vector2, f32)>,
+    // Power-ups picked up since spawning.
+    effects: Vec<Effect>,
+    // What the HUD was last told about `effects`.
+    effect_labels: Vec<String>,
+    // Whether the `DoubleJump` power-up has been used since the last landing.
+    used_air_jump: bool,
+    // In health mode, how many hits the player can take. 0 when not in health
+    // mode, so the first hit eats the player.
+    max_health: u32,
+    health: u32,
+    /// In health mode, how fast a hit knocks the player back.
+    #[export]
+    knockback_speed: f32,
+    /// In health mode, how long after a hit before the player can be hurt
+    /// again.
+    #[export]
+    invulnerability_sec: f32,
+    invulnerable_remaining_sec: f32,
     #[export]
     debug_collisions: bool,
+    /// When a landing error is reported, save a test scene that reproduces the
+    /// jump that caused it to `res://test_scenes/`, and add it to
+    /// `test_scenes.tres`. Only works when running from the project, not from
+    /// an export.
+    #[export]
+    save_repro_scenes: bool,
+    // The player before the most recent jump, and the jump's strength.
+    last_jump: Option<(PlayerInfo, f32)>,
     base: Base<CharacterBody2D>,
 }
 
@@ -59,12 +211,41 @@ impl ICharacterBody2D for Player {
             direction: Direction::Left,
             target_velocity: Vector2::ZERO,
             max_jump_strength: 20.0,
+            jump_normal_blend: 0.0,
             fall_acceleration: 75.0,
             on_surface: false,
             on_ceiling: false,
+            on_wall: false,
+            wall_jump_angle: 45.0,
+            let_go_tap_ms: 100.0,
             shimmy_speed: 75.0,
             shimmy_dest: None,
+            corner_shimmy: None,
+            slide_friction: 40.0,
+            slide: None,
+            bounce_restitution: 0.8,
+            sprite_scale: Vector2::ONE,
+            squash_tween: None,
+            wind_zones: Vec::new(),
+            gravity_zones: Vec::new(),
+            gravity: Vector2::DOWN,
+            camera_follows_gravity: false,
+            death_cause: None,
+            inherit_platform_velocity: true,
+            platform: None,
+            platform_velocity: Vector2::ZERO,
+            grapple: None,
+            effects: Vec::new(),
+            effect_labels: Vec::new(),
+            used_air_jump: false,
+            max_health: 0,
+            health: 0,
+            knockback_speed: 250.0,
+            invulnerability_sec: 1.5,
+            invulnerable_remaining_sec: 0.0,
             debug_collisions: false,
+            save_repro_scenes: false,
+            last_jump: None,
             base,
         }
     }
@@ -81,6 +262,7 @@ impl ICharacterBody2D for Player {
 
             let frame = if self.on_surface { 0 } else { 3 };
             sprite.set_frame(frame);
+            self.sprite_scale = sprite.get_scale();
         }
 
         if Engine::singleton().is_editor_hint() {
@@ -104,9 +286,17 @@ impl ICharacterBody2D for Player {
     }
 
     fn physics_process(&mut self, delta: f64) {
-        if Engine::singleton().is_editor_hint() {
+        if Engine::singleton().is_editor_hint() || self.death_cause.is_some() {
+            return;
+        }
+        self.update_effects(delta);
+        self.update_invulnerability(delta);
+        if self.corner_shimmy.is_some() {
+            // The tween moves the player until the shimmy finishes.
             return;
         }
+        self.update_gravity();
+        self.follow_platform(delta);
         let old_position = self.base().get_position();
         if self.shimmy_dest.is_some() {
             let shimmy_dest = self.shimmy_dest.unwrap();
@@ -133,10 +323,17 @@ impl ICharacterBody2D for Player {
             self.base_mut().set_position(new_position);
             return;
         }
+        if self.slide.is_some() {
+            self.slide(delta);
+        }
         if !self.on_surface {
-            self.target_velocity.y += delta as f32 * self.fall_acceleration;
+            self.target_velocity += delta as f32 * self.fall_acceleration() * self.gravity;
+            self.target_velocity += delta as f32 * self.wind_force();
         }
-        let motion = self.target_velocity * delta as f32;
+        let motion = match self.grapple {
+            Some((anchor, tongue_length)) => self.swing_motion(delta, anchor, tongue_length),
+            None => self.target_velocity * delta as f32,
+        };
         let collision_opt = self.base_mut().move_and_collide(motion);
         if let Some(collision) = collision_opt {
             let new_position = self.base().get_position();
@@ -148,6 +345,7 @@ impl ICharacterBody2D for Player {
             print_collision(self.debug_collisions, &collision);
             if let Some(collider) = collision.get_collider() {
                 log!(self.debug_collisions, "Collided with {:?}", collider);
+                self.release_grapple();
 
                 if collision.get_depth() > 0.0 {
                     // The player is penetrating the wall. Move back along the
@@ -164,33 +362,78 @@ impl ICharacterBody2D for Player {
                 }
                 let mut landing_surface: Option<LandingSurface> = None;
                 let collision_position = collision.get_position();
-                if let Some(points) =
-                    get_collider_points(collider, &collision_position, self.debug_collisions)
-                {
-                    log!(self.debug_collisions, "Returned points: {points}");
-                    if let Some(index) = points.find(collision_position, None) {
+                let collision_mask = self.base().get_collision_mask();
+                let surface_material = SurfaceMaterial::of(
+                    &collider,
+                    collision_position,
+                    collision.get_normal(),
+                    collision_mask,
+                );
+                self.platform = collider.clone().try_cast::<Node2D>().ok().map(|platform| {
+                    let transform = platform.get_global_transform();
+                    (platform, transform)
+                });
+                let collider_points = get_collider_points(
+                    collider,
+                    collision.get_collider_shape(),
+                    &collision_position,
+                    collision_mask,
+                    self.debug_collisions,
+                );
+                if let Some(points) = collider_points.as_deref() {
+                    log!(self.debug_collisions, "Returned points: {points:?}");
+                    let corner = to_vec2(collision_position);
+                    if let Some(index) = points.iter().position(|point| *point == corner) {
                         log!(self.debug_collisions, "hit a corner!");
 
                         landing_surface = self.pick_side_to_land_on_from_corner(
-                            &points,
+                            points,
                             index,
                             motion,
                             collision.get_normal(),
                         );
                     } else {
-                        landing_surface = self.pick_side_to_land_on(
-                            &points,
-                            collision_position,
-                            motion,
-                            collision.get_normal(),
-                        );
+                        landing_surface = match geometry::pick_side_to_land_on(
+                            &self.lander(),
+                            points,
+                            to_vec2(collision_position),
+                            to_vec2(motion),
+                            to_vec2(collision.get_normal()),
+                        ) {
+                            Ok(surface) => surface,
+                            Err(ambiguous) => {
+                                Some(self.report_ambiguous_normal(&ambiguous, points))
+                            }
+                        };
                     }
                 }
                 log!(
                     self.debug_collisions,
-                    "Landing surface: {landing_surface:?}"
+                    "Landing surface: {landing_surface:?} ({surface_material:?})"
+                );
+                let normal = landing_surface.map_or_else(
+                    || collision.get_normal(),
+                    |surface| to_vector2(surface.normal),
                 );
+                match surface_material {
+                    SurfaceMaterial::Hazard => {
+                        self.die(DeathCause::Hazard);
+                        return;
+                    }
+                    SurfaceMaterial::Bouncy => {
+                        let velocity =
+                            self.target_velocity.bounce(normal) * self.bounce_restitution;
+                        if velocity.length() >= MIN_BOUNCE_SPEED {
+                            self.bounce(velocity, normal);
+                            return;
+                        }
+                    }
+                    SurfaceMaterial::Sticky | SurfaceMaterial::Slippery => {}
+                }
                 self.on_surface = true;
+                self.used_air_jump = false;
+                self.use_effect(PowerUpKind::SlowFall);
+                self.use_effect(PowerUpKind::FlyMagnet);
 
                 // Reverse the jump animation to land.
                 self.sprite()
@@ -200,50 +443,28 @@ impl ICharacterBody2D for Player {
                     .from_end(true)
                     .done();
 
-                let normal = landing_surface
-                    .map_or_else(|| collision.get_normal(), |surface| surface.normal);
-                let new_angle = normal.angle() + PI / 2.0;
-                self.base_mut().set_rotation(new_angle);
-                match normal {
-                    Vector2 { x, y: _ } if x > 0.5 => {
-                        self.direction = Direction::Right;
-                        self.sprite().set_flip_h(false);
-                    }
-                    Vector2 { x, y: _ } if x < -0.5 => {
-                        self.direction = Direction::Left;
-                        self.sprite().set_flip_h(true);
-                    }
-                    Vector2 { x: _, y } if y > 0.5 => {
-                        self.on_ceiling = true;
-                        let flip_h = self.direction == Direction::Left;
-                        self.sprite().set_flip_h(flip_h);
-                    }
-                    Vector2 { x: _, y } if y < -0.5 => {
-                        let flip_h = self.direction == Direction::Right;
-                        self.sprite().set_flip_h(flip_h);
-                    }
-                    normal => {
-                        godot_error!("Landed with surprise normal {normal}");
-                    }
-                } // match
+                self.orient_to_surface(normal);
 
                 // Now that we've rotated the player in the proper direction,
                 // move them so they are properly on their new surface.
                 if let Some(surface) = landing_surface {
                     // When landing on a corner, `a` represents the corner.
                     // TODO: This is totally arbitrary. Enforce/make clearer.
-                    if collision_position == surface.a {
+                    if to_vec2(collision_position) == surface.a {
                         // Land on the corner directly, pushed away by the
                         // normal. If this is too jarring in some cases, we can
                         // try starting from the player's position.
-                        let global_position = surface.a + normal * self.height_above_surface();
+                        let global_position =
+                            to_vector2(surface.a) + normal * self.height_above_surface();
                         let new_player_position = self.to_local_position(global_position);
                         self.base_mut().set_position(new_player_position);
 
                         // Shimmy more fully onto the surface over the next
                         // several frames.
                         if let Some(surface_direction) = (surface.b - surface.a).try_normalized() {
-                            let motion = (self.width() / 2.0) * surface_direction * WIDTH_MODIFIER;
+                            let motion = (self.width() / 2.0)
+                                * to_vector2(surface_direction)
+                                * WIDTH_MODIFIER;
                             if self.would_collide(motion) {
                                 log!(
                                     self.debug_collisions,
@@ -264,7 +485,7 @@ impl ICharacterBody2D for Player {
 
                         // We have the normal for the plane, we just need its
                         // distance from the origin.
-                        let d = normal.dot(surface.a);
+                        let d = normal.dot(to_vector2(surface.a));
 
                         let distance_to_surface = normal.dot(global_position) - d;
                         let desired_distance = self.height_above_surface();
@@ -274,9 +495,21 @@ impl ICharacterBody2D for Player {
                         self.base_mut().set_position(new_player_position);
 
                         // Shimmy onto the surface, if needed.
-                        if !self.can_land_on_surface(&surface) {
-                            // TODO: Shimmy around a corner?
-                            log!(self.debug_collisions, "Don't fit on surface!");
+                        if !self.lander().can_land_on_surface(&surface) {
+                            let corner_shimmy = collider_points.as_deref().and_then(|points| {
+                                geometry::find_corner_shimmy(&self.lander(), points, &surface)
+                                    .unwrap_or_else(|ambiguous| {
+                                        self.report_ambiguous_normal(&ambiguous, points);
+                                        None
+                                    })
+                            });
+                            match corner_shimmy {
+                                Some(corner_shimmy) => self.shimmy_around_corners(&corner_shimmy),
+                                None => {
+                                    log!(self.debug_collisions, "Don't fit on surface!");
+                                    log::record_warning("Don't fit on surface!".to_string());
+                                }
+                            }
                         } else {
                             if let Some(shimmy_dest) = self.find_shimmy_dest(&surface) {
                                 let motion = shimmy_dest - self.base().get_position();
@@ -292,75 +525,182 @@ impl ICharacterBody2D for Player {
                             }
                         }
                     }
+                    if surface_material == SurfaceMaterial::Slippery
+                        && self.shimmy_dest.is_none()
+                        && self.corner_shimmy.is_none()
+                    {
+                        self.start_slide(&surface);
+                    }
                     godot_print!("Player's local position: {}", self.base().get_position());
                     if self.would_collide(Vector2::ZERO) {
-                        godot_error!("Created a new collision!");
+                        report_error!("Created a new collision!");
+                        self.save_repro_scene("Created a new collision!");
                     }
                 }
             }
         }
 
         if self.on_surface {
-            if let Some(jump_strength) = self.jump_handler().bind_mut().handle_input(delta) {
-                if !self.on_ceiling {
-                    // Jump right-side up, facing `Direction`.
-                    self.base_mut().set_rotation(0.0);
-                    let flip_h = self.direction == Direction::Right;
-                    self.sprite().set_flip_h(flip_h);
-
-                    // Sometimes the rotation causes new collisions. (The change
-                    // to `flip_h` should make no difference because the
-                    // collision rectangle is centered around the player.)
-                    // Remove any overlap.
-                    let position = self.base().get_position();
-                    let bb = self.bounding_box();
-
-                    let mut base_mut = self.base_mut();
-                    if let Some(collision) = base_mut
-                        .move_and_collide_ex(Vector2::ZERO)
-                        .test_only(true)
-                        .done()
-                    {
-                        // Since this is an unusual collision - the rectangle
-                        // instantaneously changed - the depth is incorrect.
-                        // But landing on this surface previously placed the
-                        // player height/2 away from the surface, and now they
-                        // should be width/2 away, so add the difference.
-                        let diff = (bb.size.x - bb.size.y) / 2.0;
-                        let offset = collision.get_normal() * (collision.get_depth() + diff);
-                        let new_position = position + offset;
-                        base_mut.set_position(new_position);
-                    }
+            if self.is_tongue_just_pressed() {
+                let facing = self.facing();
+                if let Some(mut tongue) = self.try_tongue() {
+                    tongue.bind_mut().lick(facing);
+                }
+            }
+            // A jump buffered before landing waits until the landing's shimmy
+            // is done, so that it starts from the surface the player ends up on.
+            let jump = if self.is_shimmying() {
+                None
+            } else {
+                self.jump_handler().bind_mut().handle_input(delta)
+            };
+            if let Some(jump_strength) = jump {
+                self.last_jump = Some((self.get_player_info(), jump_strength));
+                let surface_normal = self.surface_normal();
+                let let_go = (self.on_wall || self.on_ceiling)
+                    && self.jump_handler().bind().last_press_ms() < self.let_go_tap_ms;
+                if let_go {
+                    // Fall right-side up, even from the ceiling.
+                    self.on_ceiling = false;
                 }
+                self.turn_upright();
                 if self.would_collide(Vector2::ZERO) {
-                    godot_error!("Shouldn't still have a collision!");
+                    report_error!("Shouldn't still have a collision!");
+                    self.save_repro_scene("Shouldn't still have a collision!");
                 }
 
-                self.target_velocity = self.get_jump(jump_strength);
-                self.sprite().play_ex().name("jump").done();
-                self.on_surface = false;
-                self.on_ceiling = false;
+                self.target_velocity = if let_go {
+                    log!(self.debug_collisions, "Let go of the surface");
+                    surface_normal * LET_GO_SPEED
+                } else if self.on_wall {
+                    self.get_wall_jump(jump_strength, surface_normal)
+                } else {
+                    self.get_jump(jump_strength, surface_normal)
+                };
+                if self.inherit_platform_velocity {
+                    self.target_velocity += self.platform_velocity;
+                }
+                if let Some(slide) = self.slide {
+                    self.target_velocity += slide.velocity;
+                }
+                if !let_go {
+                    self.use_effect(PowerUpKind::SuperJump);
+                }
+                self.leave_surface();
             } else {
                 self.target_velocity = Vector2::ZERO;
             }
+        } else if self.grapple.is_some() {
+            // Releasing jump lets go, launching the player along the swing.
+            if self.jump_handler().bind_mut().swing_input() {
+                self.release_grapple();
+            }
+        } else {
+            if self.is_tongue_just_pressed() {
+                self.start_grapple();
+            }
+            if self.can_air_jump() {
+                // A jump buffered before landing waits until the landing's shimmy
+                // is done, so that it starts from the surface the player ends up on.
+                let jump = if self.is_shimmying() {
+                    None
+                } else {
+                    self.jump_handler().bind_mut().handle_input(delta)
+                };
+                if let Some(jump_strength) = jump {
+                    self.air_jump(jump_strength);
+                }
+            } else {
+                self.jump_handler().bind_mut().buffer_input(delta);
+            }
         }
     }
 }
 
 #[godot_api]
 impl Player {
-    fn get_jump(&self, jump_ratio: f32) -> Vector2 {
+    // Rotate so that the player's feet face a surface with `normal`, and
+    // determine whether it is a floor, wall or ceiling.
+    fn orient_to_surface(&mut self, normal: Vector2) {
+        self.on_ceiling = false;
+        self.on_wall = false;
+        let new_angle = normal.angle() + PI / 2.0;
+        self.base_mut().set_rotation(new_angle);
+        // Floors, walls and ceilings are relative to gravity.
+        match normal.rotated(-self.gravity_rotation()) {
+            Vector2 { x, y: _ } if x > 0.5 => {
+                self.on_wall = true;
+                self.direction = Direction::Right;
+                self.sprite().set_flip_h(false);
+            }
+            Vector2 { x, y: _ } if x < -0.5 => {
+                self.on_wall = true;
+                self.direction = Direction::Left;
+                self.sprite().set_flip_h(true);
+            }
+            Vector2 { x: _, y } if y > 0.5 => {
+                self.on_ceiling = true;
+                let flip_h = self.direction == Direction::Left;
+                self.sprite().set_flip_h(flip_h);
+            }
+            Vector2 { x: _, y } if y < -0.5 => {
+                let flip_h = self.direction == Direction::Right;
+                self.sprite().set_flip_h(flip_h);
+            }
+            normal => {
+                let message = format!("Landed with surprise normal {normal}");
+                report_error!("{message}");
+                self.save_repro_scene(&message);
+            }
+        }
+    }
+
+    // The jump for `jump_ratio` of full strength, relative to gravity, and to
+    // `surface_normal` as much as `jump_normal_blend` allows.
+    fn get_jump(&self, jump_ratio: f32, surface_normal: Vector2) -> Vector2 {
         let ceiling_multiplier = match self.on_ceiling {
             true => 1.0,
             false => -1.0,
         };
-        let jump_strength = jump_ratio * self.max_jump_strength * ceiling_multiplier;
+        let jump_strength = jump_ratio * self.max_jump_strength() * ceiling_multiplier;
         const JUMP_ANGLE: f32 = 5.0 * PI / 16.0;
         let jump_angle = match self.direction {
             Direction::Left => JUMP_ANGLE,
             Direction::Right => -JUMP_ANGLE,
         } * ceiling_multiplier;
-        Vector2::new(0.0, jump_strength).rotated(jump_angle)
+        // The normal of a flat floor or ceiling, for which the blend makes no
+        // difference.
+        let flat_normal = if self.on_ceiling {
+            self.gravity
+        } else {
+            -self.gravity
+        };
+        let slope_angle = flat_normal.angle_to(surface_normal) * self.jump_normal_blend;
+        Vector2::new(0.0, jump_strength).rotated(jump_angle + self.gravity_rotation() + slope_angle)
+    }
+
+    // Like `get_jump()`, but pushing away from a wall with `wall_normal` at
+    // `wall_jump_angle`, towards up (relative to gravity).
+    fn get_wall_jump(&self, jump_ratio: f32, wall_normal: Vector2) -> Vector2 {
+        let up = -self.gravity;
+        let towards_up = if wall_normal.cross(up) > 0.0 {
+            1.0
+        } else {
+            -1.0
+        };
+        let angle = self.wall_jump_angle.to_radians() * towards_up;
+        wall_normal.rotated(angle) * jump_ratio * self.max_jump_strength()
+    }
+
+    // The normal of the surface the player is on. Landing rotates the player
+    // so that their feet face the surface.
+    fn surface_normal(&self) -> Vector2 {
+        Vector2::UP.rotated(self.base().get_rotation())
+    }
+
+    // The rotation that takes normal gravity (+Y) to the current gravity.
+    fn gravity_rotation(&self) -> f32 {
+        Vector2::DOWN.angle_to(self.gravity)
     }
 
     fn idle_timer(&self) -> Gd<Timer> {
@@ -395,10 +735,36 @@ impl Player {
             .try_get_node_as::<AnimatedSprite2D>("AnimatedSprite2D")
     }
 
+    // Scenes saved before the player had a tongue won't have one.
+    fn try_tongue(&self) -> Option<Gd<Tongue>> {
+        self.base().try_get_node_as::<Tongue>("Tongue")
+    }
+
+    fn is_tongue_just_pressed(&self) -> bool {
+        self.try_tongue()
+            .is_some_and(|mut tongue| tongue.bind_mut().is_tongue_just_pressed())
+    }
+
+    // The global direction the player faces, relative to gravity.
+    fn facing(&self) -> Vector2 {
+        let facing = match self.direction {
+            Direction::Left => Vector2::LEFT,
+            Direction::Right => Vector2::RIGHT,
+        };
+        facing.rotated(self.gravity_rotation())
+    }
+
     fn jump_handler(&self) -> Gd<JumpHandler> {
         self.base().get_node_as::<JumpHandler>("JumpHandler")
     }
 
+    // Emitted once the death animation finishes. See `death_cause()` for
+    // why.
+    #[signal]
+    fn died(player: Gd<Node2D>);
+    #[signal]
+    fn effects_changed(effects: PackedStringArray);
+
     // Prevent future "jump" actions from working. Used when a level is over,
     // and a new level creates new players, so there is no need to reenable.
     #[func]
@@ -406,26 +772,705 @@ impl Player {
         self.jump_handler().bind_mut().disable();
     }
 
+    pub fn on_ceiling(&self) -> bool {
+        self.on_ceiling
+    }
+
+    pub fn on_surface(&self) -> bool {
+        self.on_surface
+    }
+
+    // Whether the player is moving onto a surface after landing on a corner,
+    // either along the surface or around the corners of the collider.
+    pub fn is_shimmying(&self) -> bool {
+        self.shimmy_dest.is_some() || self.corner_shimmy.is_some()
+    }
+
     pub fn get_player_info(&self) -> PlayerInfo {
         PlayerInfo {
             pos: self.base().get_position(),
             vel: self.target_velocity,
             dir: self.direction,
+            rotation: self.base().get_rotation(),
+            on_surface: self.on_surface,
+            on_ceiling: self.on_ceiling,
         }
     }
 
     pub fn set_player_info(&mut self, info: &PlayerInfo) {
         self.base_mut().set_position(info.pos);
+        self.base_mut().set_rotation(info.rotation);
         self.target_velocity = info.vel;
         self.direction = info.dir;
+        self.on_surface = info.on_surface;
+        self.on_ceiling = info.on_ceiling;
+    }
+
+    // Keep the player in the frame of whatever they're standing on, so they
+    // move and rotate along with moving platforms. This includes any shimmy in
+    // progress.
+    fn follow_platform(&mut self, delta: f64) {
+        self.platform_velocity = Vector2::ZERO;
+        let Some((platform, last_transform)) = self.platform.clone() else {
+            return;
+        };
+        if !self.on_surface || !platform.is_instance_valid() {
+            self.platform = None;
+            return;
+        }
+        let transform = platform.get_global_transform();
+        if transform == last_transform {
+            return;
+        }
+        let platform_motion = transform * last_transform.affine_inverse();
+        let old_transform = self.base().get_global_transform();
+        let new_transform = platform_motion * old_transform;
+        self.base_mut().set_global_transform(new_transform);
+        if let Some(shimmy_dest) = self.shimmy_dest {
+            let global_dest = platform_motion * self.to_global_position(shimmy_dest);
+            self.shimmy_dest = Some(self.to_local_position(global_dest));
+        }
+        self.platform_velocity = (new_transform.origin - old_transform.origin) / delta as f32;
+        self.platform = Some((platform, transform));
+    }
+
+    // Move around the corners of `corner_shimmy` onto its surface, rotating
+    // through each corner.
+    fn shimmy_around_corners(&mut self, corner_shimmy: &CornerShimmy) {
+        let Some(&first_corner) = corner_shimmy.corners.first() else {
+            return;
+        };
+        let normal = self.surface_normal();
+        let final_normal = to_vector2(corner_shimmy.surface.normal);
+        log!(
+            self.debug_collisions,
+            "Shimmying around corners {:?}",
+            corner_shimmy.corners
+        );
+
+        // The normals of the edges between corners point to the same side of
+        // the path as the current surface's.
+        let corners: Vec<Vector2> = corner_shimmy
+            .corners
+            .iter()
+            .map(|corner| to_vector2(*corner))
+            .collect();
+        let from_player = to_vector2(first_corner) - self.get_global_position();
+        let side = from_player.cross(normal).signum();
+        let mut normals = vec![normal];
+        for edge in corners.windows(2) {
+            let direction = edge[1] - edge[0];
+            let edge_normal = direction.orthogonal().normalized();
+            normals.push(if direction.cross(edge_normal).signum() == side {
+                edge_normal
+            } else {
+                -edge_normal
+            });
+        }
+        normals.push(final_normal);
+
+        // Stop at each corner, halfway between its edges' normals.
+        let mut waypoints: Vec<(Vector2, Vector2)> = corners
+            .iter()
+            .enumerate()
+            .map(|(i, corner)| {
+                let corner_normal = (normals[i] + normals[i + 1])
+                    .try_normalized()
+                    .unwrap_or(normals[i]);
+                let position = *corner + corner_normal * self.height_above_surface();
+                (position, corner_normal)
+            })
+            .collect();
+        let surface_direction =
+            to_vector2(corner_shimmy.surface.b - corner_shimmy.surface.a).normalized();
+        let last_corner = *corners.last().unwrap();
+        let destination = last_corner
+            + surface_direction * (self.width() / 2.0) * WIDTH_MODIFIER
+            + final_normal * self.height_above_surface();
+        waypoints.push((destination, final_normal));
+
+        let Some(mut tween) = self.base_mut().create_tween() else {
+            return;
+        };
+        let mut position = self.get_global_position();
+        let mut rotation = self.base().get_rotation();
+        for (waypoint, waypoint_normal) in waypoints {
+            let duration = (position.distance_to(waypoint) / self.shimmy_speed).max(0.05) as f64;
+            // Take the short way around.
+            rotation += Vector2::from_angle(rotation)
+                .angle_to(Vector2::from_angle(waypoint_normal.angle() + PI / 2.0));
+            tween.tween_property(
+                &self.to_gd(),
+                "position",
+                &self.to_local_position(waypoint).to_variant(),
+                duration,
+            );
+            tween.parallel();
+            tween.tween_property(&self.to_gd(), "rotation", &rotation.to_variant(), duration);
+            position = waypoint;
+        }
+        tween.tween_callback(&self.base().callable("on_corner_shimmy_finished"));
+        self.platform = None;
+        self.sprite().play_ex().name("shimmy").done();
+        self.corner_shimmy = Some((tween, final_normal));
+    }
+
+    #[func]
+    fn on_corner_shimmy_finished(&mut self) {
+        let Some((_, normal)) = self.corner_shimmy.take() else {
+            return;
+        };
+        self.orient_to_surface(normal);
+        self.sprite().play_ex().name("default").done();
+        if self.would_collide(Vector2::ZERO) {
+            report_error!("Shimmied around a corner into a collision!");
+        }
+    }
+
+    // Before jumping or falling, rotate right-side up (relative to gravity),
+    // facing `Direction`, unless on the ceiling.
+    fn turn_upright(&mut self) {
+        if self.on_ceiling {
+            return;
+        }
+        let upright = self.gravity_rotation();
+        self.base_mut().set_rotation(upright);
+        let flip_h = self.direction == Direction::Right;
+        self.sprite().set_flip_h(flip_h);
+
+        // Sometimes the rotation causes new collisions. (The change
+        // to `flip_h` should make no difference because the
+        // collision rectangle is centered around the player.)
+        // Remove any overlap.
+        let position = self.base().get_position();
+        let bb = self.bounding_box();
+
+        let mut base_mut = self.base_mut();
+        if let Some(collision) = base_mut
+            .move_and_collide_ex(Vector2::ZERO)
+            .test_only(true)
+            .done()
+        {
+            // Since this is an unusual collision - the rectangle
+            // instantaneously changed - the depth is incorrect.
+            // But landing on this surface previously placed the
+            // player height/2 away from the surface, and now they
+            // should be width/2 away, so add the difference.
+            let diff = (bb.size.x - bb.size.y) / 2.0;
+            let offset = collision.get_normal() * (collision.get_depth() + diff);
+            let new_position = position + offset;
+            base_mut.set_position(new_position);
+        }
+    }
+
+    // Become airborne, e.g. after jumping, moving at `target_velocity`.
+    fn leave_surface(&mut self) {
+        self.platform = None;
+        self.slide = None;
+        self.sprite().play_ex().name("jump").done();
+        self.on_surface = false;
+        self.on_ceiling = false;
+        self.on_wall = false;
+    }
+
+    // Rebound off a bouncy surface with `normal` instead of landing, staying
+    // airborne. Like jumping, face the direction of travel, right-side up.
+    fn bounce(&mut self, velocity: Vector2, normal: Vector2) {
+        log!(self.debug_collisions, "Bounced with velocity {velocity}");
+        self.target_velocity = velocity;
+        self.platform = None;
+        let relative_velocity = velocity.rotated(-self.gravity_rotation());
+        if relative_velocity.x > 0.0 {
+            self.direction = Direction::Right;
+        } else if relative_velocity.x < 0.0 {
+            self.direction = Direction::Left;
+        }
+        self.turn_upright();
+        self.squash(normal);
+    }
+
+    // Briefly flatten the sprite against a surface with `normal`.
+    fn squash(&mut self, normal: Vector2) {
+        if let Some(mut tween) = self.squash_tween.take() {
+            tween.kill();
+        }
+        let mut sprite = self.sprite();
+        sprite.set_scale(self.sprite_scale);
+        let squash = if normal.x.abs() > normal.y.abs() {
+            Vector2::new(0.6, 1.3)
+        } else {
+            Vector2::new(1.3, 0.6)
+        };
+        let Some(mut tween) = self.base_mut().create_tween() else {
+            return;
+        };
+        tween.tween_property(
+            &sprite,
+            "scale",
+            &(self.sprite_scale * squash).to_variant(),
+            SQUASH_SEC,
+        );
+        tween.tween_property(
+            &sprite,
+            "scale",
+            &self.sprite_scale.to_variant(),
+            SQUASH_SEC,
+        );
+        self.squash_tween = Some(tween);
+    }
+
+    // Keep the part of the landing velocity that runs along `surface`. Gravity
+    // may start the slide even if that is zero.
+    fn start_slide(&mut self, surface: &LandingSurface) {
+        let a = to_vector2(surface.a);
+        let b = to_vector2(surface.b);
+        let Some(surface_direction) = (b - a).try_normalized() else {
+            return;
+        };
+        let velocity = surface_direction * self.target_velocity.dot(surface_direction);
+        self.slide = Some(Slide { velocity, a, b });
+    }
+
+    // Slide along a slippery surface, pulled by the part of gravity that runs
+    // along it and slowed by friction. Stop if the slide runs out of speed
+    // (and gravity can't overcome friction) or something is in the way. Fall
+    // once past either end of the surface.
+    fn slide(&mut self, delta: f64) {
+        let Some(mut slide) = self.slide.take() else {
+            return;
+        };
+        if !self.on_surface {
+            return;
+        }
+        let delta = delta as f32;
+        let Some(surface_direction) = (slide.b - slide.a).try_normalized() else {
+            return;
+        };
+        let gravity = self.fall_acceleration() * self.gravity.dot(surface_direction);
+        let speed = slide.velocity.dot(surface_direction) + gravity * delta;
+        let friction = self.slide_friction * delta;
+        let speed = if speed.abs() <= friction {
+            if gravity.abs() <= self.slide_friction {
+                // Static friction holds the player in place.
+                return;
+            }
+            0.0
+        } else {
+            speed - friction * speed.signum()
+        };
+        slide.velocity = surface_direction * speed;
+
+        let motion = slide.velocity * delta;
+        if self.would_collide(motion) {
+            return;
+        }
+        let global_position = self.get_global_position() + motion;
+        let new_position = self.to_local_position(global_position);
+        self.base_mut().set_position(new_position);
+
+        let along = (global_position - slide.a).dot(surface_direction);
+        if along < 0.0 || along > slide.a.distance_to(slide.b) {
+            log!(self.debug_collisions, "Slid off the surface");
+            self.target_velocity = slide.velocity;
+            self.turn_upright();
+            self.leave_surface();
+            return;
+        }
+        self.slide = Some(slide);
+    }
+
+    // Stop moving and play the death animation for `cause`, then let the level
+    // handle the player's death.
+    pub fn die(&mut self, cause: DeathCause) {
+        if self.death_cause.is_some() {
+            return;
+        }
+        log!(self.debug_collisions, "Player died: {cause:?}");
+        self.death_cause = Some(cause);
+        self.target_velocity = Vector2::ZERO;
+        self.platform = None;
+        self.slide = None;
+        self.shimmy_dest = None;
+        if let Some((mut tween, _)) = self.corner_shimmy.take() {
+            tween.kill();
+        }
+        self.release_grapple();
+        self.invulnerable_remaining_sec = 0.0;
+        self.sprite().set_visible(true);
+        // The level clears the HUD's effects when it removes the player, and
+        // `die` may be called by the level itself, so don't emit
+        // `effects_changed` here.
+        self.effects.clear();
+        self.disable_jumping();
+
+        let on_death_animation_finished = self.base().callable("on_death_animation_finished");
+        let Some(mut tween) = self.base_mut().create_tween() else {
+            return;
+        };
+        let duration = match cause {
+            DeathCause::Hazard => {
+                // Flash red and hop up, fading away.
+                let mut sprite = self.sprite();
+                sprite.stop();
+                sprite.set_modulate(Color::RED);
+                let position = self.base().get_position();
+                let up = Vector2::UP.rotated(self.base().get_rotation());
+                tween.set_parallel();
+                tween.tween_property(
+                    &self.to_gd(),
+                    "position",
+                    &(position + up * 24.0).to_variant(),
+                    HAZARD_DEATH_SEC,
+                );
+                tween.tween_property(
+                    &sprite,
+                    "modulate",
+                    &Color::from_rgba(1.0, 0.0, 0.0, 0.0).to_variant(),
+                    HAZARD_DEATH_SEC,
+                );
+                HAZARD_DEATH_SEC
+            }
+            DeathCause::Drowned => {
+                // Sink, fading to the color of the water.
+                let sprite = self.sprite();
+                let position = self.base().get_position();
+                tween.set_parallel();
+                tween.tween_property(
+                    &self.to_gd(),
+                    "position",
+                    &(position + self.gravity * 32.0).to_variant(),
+                    DROWNED_DEATH_SEC,
+                );
+                tween.tween_property(
+                    &sprite,
+                    "modulate",
+                    &Color::from_rgba(0.2, 0.4, 1.0, 0.0).to_variant(),
+                    DROWNED_DEATH_SEC,
+                );
+                DROWNED_DEATH_SEC
+            }
+            // Already off screen.
+            DeathCause::Fell => 0.0,
+        };
+        if let Some(mut callback) = tween.tween_callback(&on_death_animation_finished) {
+            callback.set_delay(duration);
+        }
+    }
+
+    // Latch the tongue onto a grapple point, if one is in reach, and swing
+    // from it.
+    fn start_grapple(&mut self) {
+        let Some(mut tongue) = self.try_tongue() else {
+            return;
+        };
+        let anchor = tongue.bind_mut().grapple(-self.gravity);
+        if let Some(anchor) = anchor {
+            log!(self.debug_collisions, "Grappled {anchor}");
+            let tongue_length = self.get_global_position().distance_to(anchor);
+            self.grapple = Some((anchor, tongue_length));
+        }
+    }
+
+    fn release_grapple(&mut self) {
+        if self.grapple.take().is_some() {
+            if let Some(mut tongue) = self.try_tongue() {
+                tongue.bind_mut().release();
+            }
+        }
+    }
+
+    // The motion for this frame while swinging. The tongue acts like a rope:
+    // it removes any velocity that would stretch it, and keeps the player
+    // within `tongue_length` of the `anchor`. What remains is the tangential
+    // velocity, which the player keeps when letting go.
+    fn swing_motion(&mut self, delta: f64, anchor: Vector2, tongue_length: f32) -> Vector2 {
+        let position = self.get_global_position();
+        let offset = position - anchor;
+        if offset.length() >= tongue_length {
+            if let Some(radial) = offset.try_normalized() {
+                let outward_speed = self.target_velocity.dot(radial);
+                if outward_speed > 0.0 {
+                    self.target_velocity -= radial * outward_speed;
+                }
+            }
+        }
+        let destination = position + self.target_velocity * delta as f32;
+        let offset = destination - anchor;
+        let destination = match offset.try_normalized() {
+            Some(direction) if offset.length() > tongue_length => {
+                anchor + direction * tongue_length
+            }
+            _ => destination,
+        };
+        destination - position
+    }
+
+    // Turn on health mode, with full health, or turn it off with 0.
+    pub fn set_max_health(&mut self, max_health: u32) {
+        self.max_health = max_health;
+        self.health = max_health;
+    }
+
+    pub fn has_health(&self) -> bool {
+        self.max_health > 0
+    }
+
+    pub fn health(&self) -> u32 {
+        self.health
+    }
+
+    pub fn max_health(&self) -> u32 {
+        self.max_health
+    }
+
+    // In health mode, lose health from a hit in `hit_direction` and get
+    // knocked back, unless the player is still invulnerable from the last hit.
+    // Returns whether the player survived.
+    pub fn take_hit(&mut self, hit_direction: Vector2) -> bool {
+        if self.invulnerable_remaining_sec > 0.0 {
+            return true;
+        }
+        self.health = self.health.saturating_sub(1);
+        log!(
+            self.debug_collisions,
+            "Player hit! Health: {}/{}",
+            self.health,
+            self.max_health
+        );
+        if self.health == 0 {
+            return false;
+        }
+        self.invulnerable_remaining_sec = self.invulnerability_sec;
+        if self.corner_shimmy.is_some() {
+            // Let the shimmy finish rather than knocking the player out of it.
+            return true;
+        }
+        self.release_grapple();
+        self.shimmy_dest = None;
+        if self.on_surface {
+            self.turn_upright();
+            self.leave_surface();
+        }
+        let knockback = (hit_direction - self.gravity)
+            .try_normalized()
+            .unwrap_or(-self.gravity);
+        self.target_velocity = knockback * self.knockback_speed;
+        true
+    }
+
+    // Flash the sprite while invulnerable.
+    fn update_invulnerability(&mut self, delta: f64) {
+        if self.invulnerable_remaining_sec <= 0.0 {
+            return;
+        }
+        self.invulnerable_remaining_sec -= delta as f32;
+        let visible = self.invulnerable_remaining_sec <= 0.0
+            || (self.invulnerable_remaining_sec * FLASH_RATE) as i32 % 2 == 0;
+        self.sprite().set_visible(visible);
+    }
+
+    // Start the power-up's effect, replacing any of the same kind.
+    pub fn add_effect(&mut self, effect: Effect) {
+        log!(self.debug_collisions, "Picked up {effect:?}");
+        self.effects.retain(|active| active.kind != effect.kind);
+        self.effects.push(effect);
+        self.report_effects();
+    }
+
+    fn has_effect(&self, kind: PowerUpKind) -> bool {
+        self.effects.iter().any(|effect| effect.kind == kind)
+    }
+
+    // Remove a one-shot effect of `kind`, now that it has been used.
+    fn use_effect(&mut self, kind: PowerUpKind) {
+        self.effects
+            .retain(|effect| effect.kind != kind || effect.remaining_sec.is_some());
+    }
+
+    fn update_effects(&mut self, delta: f64) {
+        for effect in &mut self.effects {
+            if let Some(remaining_sec) = &mut effect.remaining_sec {
+                *remaining_sec -= delta as f32;
+            }
+        }
+        self.effects.retain(|effect| {
+            effect
+                .remaining_sec
+                .is_none_or(|remaining_sec| remaining_sec > 0.0)
+        });
+        if self.has_effect(PowerUpKind::FlyMagnet) {
+            self.attract_flies(delta);
+        }
+        self.report_effects();
+    }
+
+    // Tell the HUD about the active effects, if they have changed.
+    fn report_effects(&mut self) {
+        let labels: Vec<String> = self.effects.iter().map(Effect::label).collect();
+        if labels == self.effect_labels {
+            return;
+        }
+        self.effect_labels = labels;
+        let labels: PackedStringArray = self
+            .effect_labels
+            .iter()
+            .map(|label| GString::from(label.as_str()))
+            .collect();
+        self.signals().effects_changed().emit(&labels);
+    }
+
+    // The product of the strengths of all active effects of `kind`.
+    fn effect_strength(&self, kind: PowerUpKind) -> f32 {
+        self.effects
+            .iter()
+            .filter(|effect| effect.kind == kind)
+            .map(|effect| effect.strength)
+            .product()
+    }
+
+    fn max_jump_strength(&self) -> f32 {
+        self.max_jump_strength * self.effect_strength(PowerUpKind::SuperJump)
+    }
+
+    fn fall_acceleration(&self) -> f32 {
+        self.fall_acceleration / self.effect_strength(PowerUpKind::SlowFall)
+    }
+
+    fn can_air_jump(&self) -> bool {
+        !self.used_air_jump && self.grapple.is_none() && self.has_effect(PowerUpKind::DoubleJump)
+    }
+
+    fn air_jump(&mut self, jump_strength: f32) {
+        log!(self.debug_collisions, "Air jump: {jump_strength}");
+        self.used_air_jump = true;
+        self.target_velocity = self.get_jump(jump_strength, -self.gravity);
+        self.use_effect(PowerUpKind::DoubleJump);
+        self.use_effect(PowerUpKind::SuperJump);
+        self.sprite().play_ex().name("jump").done();
+    }
+
+    // Pull nearby flies toward the player, for the `FlyMagnet` power-up.
+    fn attract_flies(&self, delta: f64) {
+        let Some(mut scene_tree) = self.base().get_tree() else {
+            return;
+        };
+        let position = self.get_global_position();
+        for group in PREY_GROUPS {
+            for node in scene_tree.get_nodes_in_group(group).iter_shared() {
+                let Ok(mut fly) = node.try_cast::<Node2D>() else {
+                    continue;
+                };
+                let fly_position = fly.get_global_position();
+                if fly_position.distance_to(position) <= MAGNET_RANGE {
+                    fly.set_global_position(
+                        fly_position.move_toward(position, MAGNET_SPEED * delta as f32),
+                    );
+                }
+            }
+        }
+    }
+
+    pub fn enter_wind_zone(&mut self, wind_zone: Gd<WindZone>) {
+        self.wind_zones.push(wind_zone);
+    }
+
+    pub fn exit_wind_zone(&mut self, wind_zone: &Gd<WindZone>) {
+        self.wind_zones.retain(|zone| zone != wind_zone);
+    }
+
+    // The combined force of every wind zone the player is in.
+    fn wind_force(&mut self) -> Vector2 {
+        self.wind_zones.retain(|zone| zone.is_instance_valid());
+        self.wind_zones
+            .iter()
+            .map(|zone| zone.bind().current_force())
+            .fold(Vector2::ZERO, |total, force| total + force)
+    }
+
+    pub fn enter_gravity_zone(&mut self, gravity_zone: Gd<GravityZone>) {
+        self.gravity_zones.push(gravity_zone);
+    }
+
+    pub fn exit_gravity_zone(&mut self, gravity_zone: &Gd<GravityZone>) {
+        self.gravity_zones.retain(|zone| zone != gravity_zone);
+    }
+
+    // Take gravity from the most recent gravity zone, if any, and rotate the
+    // camera to match if needed.
+    fn update_gravity(&mut self) {
+        self.gravity_zones.retain(|zone| zone.is_instance_valid());
+        let (gravity, rotate_camera) = match self.gravity_zones.last() {
+            Some(zone) => {
+                let zone = zone.bind();
+                (zone.gravity(), zone.rotates_camera())
+            }
+            None => (Vector2::DOWN, false),
+        };
+        self.gravity = gravity;
+
+        if !rotate_camera && !self.camera_follows_gravity {
+            return;
+        }
+        let Some(mut camera) = self.base().try_get_node_as::<Camera2D>("Camera2D") else {
+            return;
+        };
+        if !self.camera_follows_gravity {
+            self.camera_follows_gravity = true;
+            camera.set_ignore_rotation(false);
+            camera.set_rotation_smoothing_enabled(true);
+        }
+        // Leaving a zone that rotated the camera turns it back to normal.
+        let camera_rotation = if rotate_camera {
+            self.gravity_rotation()
+        } else {
+            0.0
+        };
+        camera.set_global_rotation(camera_rotation);
+    }
+
+    pub fn death_cause(&self) -> Option<DeathCause> {
+        self.death_cause
+    }
+
+    // Takes `&self` so that the level can check `death_cause()` in response.
+    #[func]
+    fn on_death_animation_finished(&self) {
+        let player = self.to_gd().upcast::<Node2D>();
+        player.clone().emit_signal("died", &[player.to_variant()]);
+    }
+
+    // If enabled, save a test scene that reproduces the most recent jump. Only
+    // one scene is saved per jump.
+    fn save_repro_scene(&mut self, message: &str) {
+        if !self.save_repro_scenes {
+            return;
+        }
+        let Some((player_info, jump_strength)) = self.last_jump.take() else {
+            godot_print!("No jump to reproduce \"{message}\"");
+            return;
+        };
+        let Some(level) = self
+            .base()
+            .get_parent()
+            .and_then(|parent| parent.try_cast::<TileMapLayer>().ok())
+        else {
+            report_error!("Player needs to be in a level to save a repro scene");
+            return;
+        };
+        let max_time_ms = self.jump_handler().bind().max_time_ms();
+        repro::save_repro_scene(&level, &player_info, jump_strength, max_time_ms, message);
     }
 
     fn get_global_position(&self) -> Vector2 {
+        self.to_global_position(self.base().get_position())
+    }
+
+    fn to_global_position(&self, local_position: Vector2) -> Vector2 {
         self.base()
             .get_parent()
             .unwrap()
             .cast::<Node2D>()
-            .to_global(self.base().get_position())
+            .to_global(local_position)
     }
 
     fn to_local_position(&self, global_position: Vector2) -> Vector2 {
@@ -436,115 +1481,53 @@ impl Player {
             .to_local(global_position)
     }
 
-    fn pick_side_to_land_on(
-        &self,
-        points: &PackedVector2Array,
-        collision_position: Vector2,
-        player_motion: Vector2,
-        collision_normal: Vector2,
-    ) -> Option<LandingSurface> {
-        for i in 0..points.len() {
-            let i2 = next_point(points, i);
-            let a = points[i];
-            let b = points[i2];
-
-            // Construct a plane from a normal and a point. See
-            // https://docs.godotengine.org/en/stable/tutorials/math/vectors_advanced.html#constructing-a-plane-in-2d
-            let n_opt = math::normal(a, b, player_motion);
-            if n_opt.is_none() {
-                // This side is too small. Skip it.
-                continue;
-            }
-            let n = n_opt.unwrap();
-            let surface = LandingSurface { a, b, normal: n };
-            if surface.hit_by(collision_position, collision_normal) {
-                if !self.can_land_on_surface(&surface) {
-                    let can_land_on_surface = |s: &LandingSurface| self.can_land_on_surface(s);
-                    let next_surface =
-                        LandingSurface::find_surface(points, i2, next_point(points, i2))
-                            .filter(can_land_on_surface);
-                    let prior_surface =
-                        LandingSurface::find_surface(points, i, prior_point(points, i))
-                            .filter(can_land_on_surface);
-                    match (next_surface, prior_surface) {
-                        (None, None) => (),
-                        (None, Some(prior_surface)) => return Some(prior_surface),
-                        (Some(next_surface), None) => return Some(next_surface),
-                        (Some(next_surface), Some(prior_surface)) => {
-                            // Pick the closer corner:
-                            let distance_squared = |s: &LandingSurface| {
-                                self.get_global_position().distance_squared_to(s.a)
-                            };
-                            return if distance_squared(&next_surface)
-                                < distance_squared(&prior_surface)
-                            {
-                                Some(next_surface)
-                            } else {
-                                Some(prior_surface)
-                            };
-                        }
-                    }
-                }
-                return Some(surface);
-            }
+    // The parts of the player that matter when picking a surface to land on.
+    fn lander(&self) -> Lander {
+        Lander {
+            position: to_vec2(self.get_global_position()),
+            min_surface_length: self.width() * WIDTH_MODIFIER,
         }
-        None
     }
 
     fn pick_side_to_land_on_from_corner(
         &self,
-        points: &PackedVector2Array,
+        points: &[Vec2],
         index: usize,
         player_motion: Vector2,
         collision_normal: Vector2,
     ) -> Option<LandingSurface> {
-        if player_motion.is_zero_approx() {
-            // I hope to be able to avoid this by properly positioning the player such that new
-            // collisions are not generated.
-            godot_error!("No player motion!");
-            return None;
-        }
-
-        let mut landing_surface_a =
-            Self::pick_adjacent_side(points, index, prior_point, player_motion);
-        let mut landing_surface_b =
-            Self::pick_adjacent_side(points, index, next_point, player_motion);
-        // First pick the surface whose normal is closest to the collision normal.
-        // Since we're dealing with normals, we can just use the one with the dot
-        // product that is larger.
-        if landing_surface_b.normal.dot(collision_normal)
-            > landing_surface_a.normal.dot(collision_normal)
-        {
-            std::mem::swap(&mut landing_surface_a, &mut landing_surface_b);
-        }
-
-        for surface in [&landing_surface_a, &landing_surface_b] {
-            if self.can_land_on_surface(surface) {
-                // The surface picked the normal based on the player motion,
-                // which I would expect to generally work - the player must be
-                // colliding from outside the polygon. But corners are funny.
-                // We might pick a side such that the player's motion looks to
-                // come from inside.
-                return Some(surface.correct_normal(points));
+        match geometry::pick_side_to_land_on_from_corner(
+            &self.lander(),
+            points,
+            index,
+            to_vec2(player_motion),
+            to_vec2(collision_normal),
+        ) {
+            Ok(surface) => Some(surface),
+            Err(LandingError::NoPlayerMotion) => {
+                report_error!("No player motion!");
+                None
+            }
+            Err(LandingError::CouldNotLandAnywhere) => {
+                report_error!("Couldn't land anywhere!");
+                None
+            }
+            Err(LandingError::AmbiguousNormal(ambiguous)) => {
+                Some(self.report_ambiguous_normal(&ambiguous, points))
             }
         }
-        // TODO: We might hit this case if you land close to the branch - then we'll have to add
-        // in the adjacent tile.
-        godot_error!("Couldn't land anywhere!");
-        return None;
     }
 
-    fn pick_adjacent_side(
-        points: &PackedVector2Array,
-        index: usize,
-        next_pt_fn: fn(&PackedVector2Array, usize) -> usize,
-        player_motion: Vector2,
+    fn report_ambiguous_normal(
+        &self,
+        ambiguous: &AmbiguousNormal,
+        points: &[Vec2],
     ) -> LandingSurface {
-        let next_point_index = next_pt_fn(points, index);
-        let a = points[index];
-        let b = points[next_point_index];
-        // `smooth_polygon` already checked the distance between these two points.
-        LandingSurface::new(a, b, player_motion).expect("surface should have a normal!")
+        report_error!("Both test points are in polygon!");
+        let [test_point, test_point2] = ambiguous.test_points;
+        godot_print!("Both test points, {test_point} and {test_point2}, are in {points:?}");
+        // Both are no good, so use the original?
+        ambiguous.surface
     }
 
     fn would_collide(&mut self, motion: Vector2) -> bool {
@@ -575,22 +1558,16 @@ impl Player {
         false
     }
 
-    // Return whether there is enough room for the player to land on the surface.
-    fn can_land_on_surface(&self, surface: &LandingSurface) -> bool {
-        surface.length_squared() > math::squared(self.width() * WIDTH_MODIFIER)
-    }
-
     // If the player is hanging off one edge of the surface or the other, return
     // the location they should shimmy to.
     fn find_shimmy_dest(&self, surface: &LandingSurface) -> Option<Vector2> {
-        if let Some(shimmy_dest) =
-            self.find_shimmy_dest_internal(surface.a, surface.b, surface.normal)
-        {
+        let a = to_vector2(surface.a);
+        let b = to_vector2(surface.b);
+        let normal = to_vector2(surface.normal);
+        if let Some(shimmy_dest) = self.find_shimmy_dest_internal(a, b, normal) {
             return Some(shimmy_dest);
         }
-        if let Some(shimmy_dest) =
-            self.find_shimmy_dest_internal(surface.b, surface.a, surface.normal)
-        {
+        if let Some(shimmy_dest) = self.find_shimmy_dest_internal(b, a, normal) {
             return Some(shimmy_dest);
         }
         None
@@ -643,25 +1620,6 @@ impl Player {
     }
 }
 
-// TODO: Probably better to return a reference? Or at least change the name?
-fn next_point(points: &PackedVector2Array, i: usize) -> usize {
-    assert!(i < points.len());
-    if i == points.len() - 1 {
-        0
-    } else {
-        i + 1
-    }
-}
-// TODO: Same
-fn prior_point(points: &PackedVector2Array, i: usize) -> usize {
-    assert!(i < points.len());
-    if i == 0 {
-        points.len() - 1
-    } else {
-        i - 1
-    }
-}
-
 fn print_collision(debug_collisions: bool, collision: &Gd<KinematicCollision2D>) {
     if !debug_collisions {
         return;
@@ -681,204 +1639,93 @@ fn print_collision(debug_collisions: bool, collision: &Gd<KinematicCollision2D>)
 // Returns global coordinates.
 fn get_collider_points(
     collider: Gd<Object>,
+    collider_shape: Option<Gd<Object>>,
     collision_position: &Vector2,
+    collision_mask: u32,
     debug_collisions: bool,
-) -> Option<PackedVector2Array> {
-    // As of this writing, the player only detects collisions with the environment, i.e.
-    // walls/ceilings/trees the player can land on. So this should always be a
-    // `TileMapLayer`.
-    if let Some(tile_map_layer) = collider.try_cast::<TileMapLayer>().ok() {
-        let local_collision = tile_map_layer.to_local(*collision_position);
-        let map_coordinates = tile_map_layer.local_to_map(local_collision);
-        if let Some(mut points) = get_collider_points_from_tile_map_layer(
+) -> Option<Vec<Vec2>> {
+    // Levels cache the merged outlines of their tiles.
+    if let Ok(mut level) = collider.clone().try_cast::<Level>() {
+        let outline = level.bind_mut().collision_outline(
+            *collision_position,
+            collision_mask,
+            debug_collisions,
+        );
+        if let Some(outline) = outline {
+            log!(debug_collisions, "Using cached outline");
+            return Some(outline);
+        }
+    }
+    // The player only detects collisions with the environment, i.e.
+    // walls/ceilings/trees the player can land on. This is usually a
+    // `TileMapLayer`, but can also be a body such as a `StaticBody2D`, in
+    // which case use the shape that was hit.
+    if let Some(tile_map_layer) = collider.clone().try_cast::<TileMapLayer>().ok() {
+        if let Some(points) = get_collider_points_from_tile_map_layer(
             &tile_map_layer,
-            map_coordinates,
-            Some(local_collision),
+            *collision_position,
+            collision_mask,
             debug_collisions,
         ) {
-            smooth_polygon(&mut points, debug_collisions);
-            return Some(points);
+            let mut polygon = to_polygon(&points);
+            smooth_polygon(&mut polygon);
+            log!(debug_collisions, "Smoothed {points} to {polygon:?}");
+            return Some(polygon);
+        }
+    } else if let Some(shape_owner) = collider_shape {
+        if let Some(points) = shape_outline(shape_owner) {
+            let mut polygon = to_polygon(&points);
+            smooth_polygon(&mut polygon);
+            log!(debug_collisions, "Smoothed {points} to {polygon:?}");
+            return Some(polygon);
         }
     } else {
-        godot_error!("Collided with something other than a TileMapLayer??");
+        report_error!("Collided with {collider} without a shape??");
     }
     None
 }
 
-// Returns global coordinates.
+// Merge the collision polygons of the tile at the collision and its
+// neighbors, returning the (global) outline closest to the collision. Works
+// for any tile shape, since Godot determines the neighbors.
 fn get_collider_points_from_tile_map_layer(
     tile_map_layer: &Gd<TileMapLayer>,
-    map_coordinates: Vector2i,
-    local_collision: Option<Vector2>,
+    collision_position: Vector2,
+    collision_mask: u32,
     debug_collisions: bool,
 ) -> Option<PackedVector2Array> {
-    let local_tile_center = tile_map_layer.map_to_local(map_coordinates);
-    let mut points_so_far: Option<PackedVector2Array> = None;
-    let half_tile_width = tile_map_layer.get_tile_set().unwrap().get_tile_size().x as f32 / 2.0;
-
-    // Extend in these directions as needed.
-    let mut directions_to_add: HashSet<Vector2i> = HashSet::new();
-    if let Some(tile_data) = tile_map_layer.get_cell_tile_data(map_coordinates) {
-        // This should correspond to the layer I've used in the editor.
-        const LAYER_ID: i32 = 0;
-        let count = tile_data.get_collision_polygons_count(LAYER_ID);
-        if count >= 1 {
-            points_so_far = Some(tile_data.get_collision_polygon_points(LAYER_ID, 0));
-            if count > 1 {
-                godot_error!("Need to handle {count} polygons in one tile!");
-            }
-        }
-
-        if let Some(mut points) = points_so_far {
-            // Check for edges that align with the tile edges. This indicates that
-            // the collision shape may extend onto the next tile. Use presence of
-            // `local_collision`, which is only set for the first call to this
-            // method (i.e. it is `None` in recursive calls).
-            if local_collision.is_some() {
-                for i in 0..points.len() {
-                    let a = points[i];
-                    let b = points[next_point(&points, i)];
-                    if a.x == b.x {
-                        if a.x == half_tile_width {
-                            directions_to_add.insert(Vector2i::RIGHT);
-                        } else if a.x == -half_tile_width {
-                            directions_to_add.insert(Vector2i::LEFT);
-                        }
-                    }
-                    if a.y == b.y {
-                        if a.y == half_tile_width {
-                            directions_to_add.insert(Vector2i::DOWN);
-                        } else if a.y == -half_tile_width {
-                            directions_to_add.insert(Vector2i::UP);
-                        }
-                    }
-                }
-            }
-            log!(debug_collisions, "\t\t\tpolygon 0: {points}");
-            for point in points.as_mut_slice() {
-                let local_point = local_tile_center + *point;
-                *point = tile_map_layer.to_global(local_point);
-                log!(debug_collisions, "\t\t\t\tglobal: {}", *point);
-            }
-            points_so_far = Some(points);
-        }
-    }
-    if local_collision.is_some() {
-        // Check for collisions that are directly on the edge of the tile. This
-        // catches some cases missed above. e.g. If a tile is empty, and the
-        // collision occurs on the edge, Godot may pick the empty tile first.
-        // These checks ensure we include the adjacent tile with a collision
-        // polygon.
-        // Note: This assumes square tiles.
-
-        if local_collision.unwrap().x == local_tile_center.x - half_tile_width {
-            directions_to_add.insert(Vector2i::LEFT);
-        } else if local_collision.unwrap().y == local_tile_center.y - half_tile_width {
-            directions_to_add.insert(Vector2i::UP);
-        }
-        // Note: I haven't been able to reproduce landing on the right or
-        // bottom edges of a tile, but the code should be similar.
-        else if local_collision.unwrap().x == local_tile_center.x + half_tile_width {
-            // Use `godot_error` just so it will stand out and I can create
-            // a repro case.
-            godot_error!("Found a repro case for right edge of tile?");
-            directions_to_add.insert(Vector2i::RIGHT);
-        } else if local_collision.unwrap().y == local_tile_center.y + half_tile_width {
-            // Use `godot_error` just so it will stand out and I can create
-            // a repro case.
-            godot_error!("Found a repro case for bottom edge of tile?");
-            directions_to_add.insert(Vector2i::DOWN);
-        }
-
-        for offset in directions_to_add {
-            let next_map_coord = map_coordinates + offset;
-            if let Some(next_points) = get_collider_points_from_tile_map_layer(
-                tile_map_layer,
-                next_map_coord,
-                None,
-                debug_collisions,
-            ) {
-                if points_so_far.is_none() {
-                    // This can only be reached if the collision is directly on
-                    // the edge. We wouldn't want to check tiles adjacent to the
-                    // empty tile anyway, since they would not be adjacent to
-                    // this one.
-                    // Note: With some hypothetical tiles/collision polygons not
-                    // in our current tile set, we might need to add tiles
-                    // adjacent to *this* one.
-                    return Some(next_points);
-                }
-                let points = points_so_far.unwrap();
-                let polygon_array = Geometry2D::singleton().merge_polygons(&next_points, &points);
-                if polygon_array.len() == 1 {
-                    log!(debug_collisions, "have a new merged polygon!");
-                    points_so_far = Some(polygon_array.at(0));
-                } else {
-                    log!(
-                        debug_collisions,
-                        "Merge resulted in {} polygons: {polygon_array}",
-                        polygon_array.len()
-                    );
-                    // This likely means that the next tile over had a
-                    // disconnected polygon. Ignore it. Restore `points_so_far`,
-                    // which was unwrapped for `merge_polygons` above.
-                    points_so_far = Some(points);
-                }
-            }
-        }
+    let local_collision = tile_map_layer.to_local(collision_position);
+    let map_coordinates = tile_map_layer.local_to_map(local_collision);
+
+    // If a tile is empty and the collision occurs on its edge, Godot may pick
+    // the empty tile, so the neighbors are needed even then.
+    let mut polygons = tile_polygons(tile_map_layer, map_coordinates, collision_mask);
+    for cell in tile_map_layer
+        .get_surrounding_cells(map_coordinates)
+        .iter_shared()
+    {
+        polygons.extend(tile_polygons(tile_map_layer, cell, collision_mask));
     }
-    return points_so_far;
-}
-
-// Modify the supplied polygon to remove unnecessary points.
-// Tile data may not line up perfectly, resulting in e.g. two points
-// that are right next to each other.
-// In addition, even if they line up perfectly, due to tiling, there will be
-// intermediate points.
-// Note: Not exhaustive for all hypothetical polygons.
-fn smooth_polygon(polygon: &mut PackedVector2Array, debug_collisions: bool) {
-    let remove_points = |polygon: &mut PackedVector2Array, stack: &mut Vec<usize>| {
-        // Remove in reverse order so the indices are still correct.
-        // `0` can be at the end; ensure it is moved to the front.
-        stack.sort();
-        while let Some(index) = stack.pop() {
-            polygon.remove(index);
-        }
-    };
-
-    // In the first pass, remove points that are effectively the same point.
-    // This way the second pass doesn't need to consider these doubles.
-    let mut stack = Vec::new();
-    for i in 0..polygon.len() {
-        let i2 = next_point(&polygon, i);
-        let a = polygon[i];
-        let b = polygon[i2];
-        if a.distance_squared_to(b) < 1.0 {
-            log!(debug_collisions, "Removing {b}!");
-            stack.push(i2);
-        }
-    }
-
-    remove_points(polygon, &mut stack);
-
-    // Combine adjacent segments that have approximately the same normals,
-    // resulting in longer continuous surfaces.
-    for i in 0..polygon.len() {
-        let i2 = next_point(&polygon, i);
-        if let Some(surface_ab) = LandingSurface::find_surface(polygon, i, i2) {
-            let i3 = next_point(&polygon, i2);
-            if let Some(surface_bc) = LandingSurface::find_surface(polygon, i2, i3) {
-                if math::same_normals_approx(surface_ab.normal, surface_bc.normal) {
-                    log!(
-                        debug_collisions,
-                        "Removing {} due to same normals!",
-                        polygon[i2]
-                    );
-                    stack.push(i2);
-                }
-            }
-        }
+    log!(
+        debug_collisions,
+        "\t\t\t{} polygons around {map_coordinates}",
+        polygons.len()
+    );
+
+    let point = to_vec2(local_collision);
+    let closest = merge_touching_polygons(polygons)
+        .into_iter()
+        .map(|merged| merged.points)
+        .min_by(|points1, points2| {
+            let distance1 = distance_to_edges(point, &to_polygon(points1));
+            let distance2 = distance_to_edges(point, &to_polygon(points2));
+            distance1.total_cmp(&distance2)
+        });
+    let mut points = closest?;
+    log!(debug_collisions, "\t\t\tmerged polygon: {points}");
+    for point in points.as_mut_slice() {
+        *point = tile_map_layer.to_global(*point);
+        log!(debug_collisions, "\t\t\t\tglobal: {}", *point);
     }
-
-    remove_points(polygon, &mut stack);
+    Some(points)
 }
@@ -0,0 +1,143 @@
+use crate::player::Player;
+use godot::classes::{Area2D, Engine, IArea2D};
+use godot::prelude::*;
+
+// The weakest a `PowerUp` may be, so that `SlowFall` never divides by zero.
+const MIN_STRENGTH: f32 = 0.1;
+
+/// What a `PowerUp` does for the player.
+#[derive(PartialEq, GodotConvert, Var, Export, Clone, Copy, Debug)]
+#[godot(via=GString)]
+pub enum PowerUpKind {
+    /// Allows a second jump in mid-air.
+    DoubleJump,
+    /// Raises the player's `max_jump_strength`.
+    SuperJump,
+    /// Reduces the player's `fall_acceleration`.
+    SlowFall,
+    /// Pulls nearby flies toward the player.
+    FlyMagnet,
+}
+
+impl Default for PowerUpKind {
+    fn default() -> Self {
+        Self::DoubleJump
+    }
+}
+
+impl PowerUpKind {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::DoubleJump => "Double jump",
+            Self::SuperJump => "Super jump",
+            Self::SlowFall => "Slow fall",
+            Self::FlyMagnet => "Fly magnet",
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            Self::DoubleJump => Color::from_rgb(0.3, 0.6, 1.0),
+            Self::SuperJump => Color::from_rgb(1.0, 0.55, 0.1),
+            Self::SlowFall => Color::from_rgb(0.85, 0.85, 1.0),
+            Self::FlyMagnet => Color::from_rgb(0.9, 0.2, 0.3),
+        }
+    }
+}
+
+// A power-up that the player has picked up.
+#[derive(Clone, Copy, Debug)]
+pub struct Effect {
+    pub kind: PowerUpKind,
+    pub strength: f32,
+    // `None` for one-shot effects, which last until used.
+    pub remaining_sec: Option<f32>,
+}
+
+impl Effect {
+    // How the effect is shown on the HUD.
+    pub fn label(&self) -> String {
+        match self.remaining_sec {
+            Some(remaining_sec) => format!("{} {}s", self.kind.label(), remaining_sec.ceil()),
+            None => self.kind.label().to_string(),
+        }
+    }
+}
+
+/// A pickup that gives the player a power-up on contact.
+#[derive(GodotClass)]
+#[class(base=Area2D, tool)]
+struct PowerUp {
+    #[export]
+    kind: PowerUpKind,
+    /// How long the effect lasts. If 0, it is one-shot instead: `DoubleJump`
+    /// gives a single mid-air jump, `SuperJump` lasts for one jump, and
+    /// `SlowFall` and `FlyMagnet` last until the player next lands.
+    #[export]
+    duration_sec: f32,
+    /// For `SuperJump`, how much to multiply `max_jump_strength` by. For
+    /// `SlowFall`, how much to divide `fall_acceleration` by. Unused by other
+    /// kinds.
+    #[export(range = (0.1, 5.0, or_greater))]
+    strength: f32,
+    #[export]
+    radius: f32,
+    base: Base<Area2D>,
+}
+
+#[godot_api]
+impl IArea2D for PowerUp {
+    fn init(base: Base<Area2D>) -> Self {
+        Self {
+            kind: PowerUpKind::DoubleJump,
+            duration_sec: 10.0,
+            strength: 1.5,
+            radius: 12.0,
+            base,
+        }
+    }
+
+    fn ready(&mut self) {
+        if Engine::singleton().is_editor_hint() {
+            return;
+        }
+        let gd = Gd::from_instance_id(self.base().instance_id());
+        self.base_mut()
+            .signals()
+            .body_entered()
+            .connect_obj(&gd, Self::on_body_entered);
+    }
+
+    fn draw(&mut self) {
+        let (radius, color) = (self.radius, self.kind.color());
+        self.base_mut().draw_circle(Vector2::ZERO, radius, color);
+        self.base_mut()
+            .draw_arc_ex(
+                Vector2::ZERO,
+                radius,
+                0.0,
+                std::f32::consts::TAU,
+                24,
+                Color::WHITE,
+            )
+            .width(2.0)
+            .done();
+    }
+}
+
+#[godot_api]
+impl PowerUp {
+    #[func]
+    fn on_body_entered(&mut self, body: Gd<Node2D>) {
+        if let Ok(mut player) = body.try_cast::<Player>() {
+            let effect = Effect {
+                kind: self.kind,
+                // The range only applies in the editor.
+                strength: self.strength.max(MIN_STRENGTH),
+                remaining_sec: (self.duration_sec > 0.0).then_some(self.duration_sec),
+            };
+            player.bind_mut().add_effect(effect);
+            self.base_mut().queue_free();
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use crate::jump_script::JumpScript;
+use crate::level::Level;
+use crate::player::{Player, PlayerInfo};
+use crate::report_error;
+use crate::scene_list::SceneList;
+use godot::classes::packed_scene::GenEditState;
+use godot::classes::{Label, Node, ResourceSaver, TileMapLayer, Time};
+use godot::global::Error;
+use godot::prelude::*;
+
+const TEST_SCENE_DIRECTORY: &str = "res://test_scenes";
+
+// The scene list that new test scenes are added to, shared by `test_main.tscn`
+// and `test_runner.tscn`. See `test_scenes/README.md`.
+const TEST_SCENE_LIST: &str = "res://test_scenes/test_scenes.tres";
+
+// Number of scenes at the end of the list that should stay at the end.
+const FINAL_SCENES: usize = 3;
+
+// Save a test scene with a copy of `level`'s tiles, and a player that jumps
+// with `jump_strength` from the state in `player_info`. `message` is the error
+// being reproduced. Returns the path of the new scene.
+pub fn save_repro_scene(
+    level: &Gd<TileMapLayer>,
+    player_info: &PlayerInfo,
+    jump_strength: f32,
+    max_time_ms: f32,
+    message: &str,
+) -> Option<GString> {
+    let name = format!(
+        "test_repro_{}",
+        Time::singleton().get_unix_time_from_system() as i64
+    );
+    let path = GString::from(format!("{TEST_SCENE_DIRECTORY}/{name}.tscn"));
+
+    let mut root = Level::new_alloc();
+    root.set_name("TileMapLayer");
+    root.set_transform(level.get_transform());
+    root.set_tile_map_data_from_array(&level.get_tile_map_data_as_array());
+    if let Some(tile_set) = level.get_tile_set() {
+        root.set_tile_set(&tile_set);
+    }
+    root.set("show_ghost", &false.to_variant());
+
+    // Keep the player as an instance of `player.tscn`, as in other test scenes.
+    let mut player = load::<PackedScene>("res://player.tscn")
+        .instantiate_ex()
+        .edit_state(GenEditState::INSTANCE)
+        .done()
+        .unwrap()
+        .cast::<Player>();
+    player.set_name("Player");
+    player.bind_mut().set_player_info(player_info);
+    add_owned_child(&mut root, player.upcast());
+
+    // Hold jump past its maximum, with the strength limited to match the
+    // original jump exactly.
+    let mut jump_script = JumpScript::new_alloc();
+    jump_script.set_name("JumpScript");
+    let jumps: PackedVector2Array = [Vector2::new(0.0, max_time_ms + 100.0)]
+        .into_iter()
+        .collect();
+    jump_script.set("jumps", &jumps.to_variant());
+    jump_script.set("max_jump_strength", &jump_strength.to_variant());
+    add_owned_child(&mut root, jump_script.upcast());
+
+    let level_path = level.get_scene_file_path();
+    let mut label = Label::new_alloc();
+    label.set_name("Label");
+    label.set_text(&format!(
+        "Generated from {level_path}, where this jump reported:\n\"{message}\"\n\nVerify that the player lands without errors. Then rename this scene and\ndescribe the scenario here."
+    ));
+    add_owned_child(&mut root, label.upcast());
+
+    let mut scene_name = Label::new_alloc();
+    scene_name.set_name("SceneName");
+    scene_name.set_position(Vector2::new(0.0, 549.0));
+    scene_name.set_text(&name);
+    add_owned_child(&mut root, scene_name.upcast());
+
+    let saved = save_scene(&root.clone().upcast(), &path);
+    root.free();
+    if !saved {
+        return None;
+    }
+    add_to_scene_list(TEST_SCENE_LIST, &path);
+    godot_print!("Saved repro scene {path}");
+    Some(path)
+}
+
+// Children must be owned by the root to be saved with it.
+fn add_owned_child(root: &mut Gd<Level>, mut child: Gd<Node>) {
+    root.add_child(&child);
+    child.set_owner(&root.clone().upcast::<Node>());
+}
+
+fn save_scene(root: &Gd<Node>, path: &GString) -> bool {
+    let mut packed_scene = PackedScene::new_gd();
+    if packed_scene.pack(root) != Error::OK {
+        report_error!("Failed to pack {path}");
+        return false;
+    }
+    if ResourceSaver::singleton()
+        .save_ex(&packed_scene)
+        .path(path)
+        .done()
+        != Error::OK
+    {
+        report_error!("Failed to save {path}");
+        return false;
+    }
+    true
+}
+
+// Add the scene at `scene_path` to the `SceneList` at `list_path`, before the
+// final scenes.
+fn add_to_scene_list(list_path: &str, scene_path: &GString) {
+    let Ok(mut list) = try_load::<SceneList>(list_path) else {
+        report_error!("{list_path} is not a SceneList");
+        return;
+    };
+    let mut scenes = list.bind().scenes();
+    let index = scenes.len().saturating_sub(FINAL_SCENES);
+    scenes.insert(index, &load::<PackedScene>(scene_path));
+    list.bind_mut().set_scenes(scenes);
+    if ResourceSaver::singleton()
+        .save_ex(&list)
+        .path(list_path)
+        .done()
+        != Error::OK
+    {
+        report_error!("Failed to save {list_path}");
+    }
+}
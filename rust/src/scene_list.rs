@@ -0,0 +1,32 @@
+use godot::prelude::*;
+
+/// A list of scenes, saved as its own resource so that several nodes (e.g.
+/// `test_main.tscn` and `test_runner.tscn`) can play the same scenes.
+#[derive(GodotClass)]
+#[class(base=Resource)]
+pub struct SceneList {
+    /// Scenes to play, in order.
+    #[export]
+    scenes: Array<Gd<PackedScene>>,
+    base: Base<Resource>,
+}
+
+#[godot_api]
+impl IResource for SceneList {
+    fn init(base: Base<Resource>) -> Self {
+        Self {
+            scenes: Array::<Gd<PackedScene>>::new(),
+            base,
+        }
+    }
+}
+
+impl SceneList {
+    pub fn scenes(&self) -> Array<Gd<PackedScene>> {
+        self.scenes.clone()
+    }
+
+    pub fn set_scenes(&mut self, scenes: Array<Gd<PackedScene>>) {
+        self.scenes = scenes;
+    }
+}
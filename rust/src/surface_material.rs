@@ -0,0 +1,111 @@
+use crate::collision_outlines::tile_polygons;
+use crate::conversions::{to_polygon, to_vec2};
+use crate::report_error;
+use geometry::polygon::is_point_in_polygon;
+use godot::classes::TileMapLayer;
+use godot::prelude::*;
+
+// Name of both the `TileSet` custom data layer and the metadata on bodies.
+const SURFACE_MATERIAL: &str = "surface_material";
+
+// How far (in pixels) to step from a collision into the collider to find the
+// tile that was hit.
+const STEP_INTO_COLLIDER: f32 = 1.0;
+
+/// How a surface affects the player when they land on it. Set with the
+/// "surface_material" custom data layer of a `TileSet` (a string), or the
+/// "surface_material" metadata of a body such as a `StaticBody2D`. Surfaces
+/// without one are `Sticky`.
+#[derive(PartialEq, GodotConvert, Var, Export, Clone, Copy, Debug)]
+#[godot(via=GString)]
+pub enum SurfaceMaterial {
+    /// The player stays where they land.
+    Sticky,
+    /// The player slides along the surface.
+    Slippery,
+    /// The player rebounds instead of landing.
+    Bouncy,
+    /// The player dies.
+    Hazard,
+}
+
+impl Default for SurfaceMaterial {
+    fn default() -> Self {
+        Self::Sticky
+    }
+}
+
+impl SurfaceMaterial {
+    // The material of `collider` where the player collided with it, at the
+    // global `collision_position` with `collision_normal`. `collision_mask`
+    // selects the tile collision polygons to check.
+    pub fn of(
+        collider: &Gd<Object>,
+        collision_position: Vector2,
+        collision_normal: Vector2,
+        collision_mask: u32,
+    ) -> Self {
+        let value = match collider.clone().try_cast::<TileMapLayer>() {
+            Ok(tile_map_layer) => tile_custom_data(
+                &tile_map_layer,
+                collision_position,
+                collision_normal,
+                collision_mask,
+            ),
+            Err(collider) => collider
+                .has_meta(SURFACE_MATERIAL)
+                .then(|| collider.get_meta(SURFACE_MATERIAL)),
+        };
+        let Some(value) = value else {
+            return Self::default();
+        };
+        if value.is_nil() || value.to_string().is_empty() {
+            return Self::default();
+        }
+        value.try_to::<SurfaceMaterial>().unwrap_or_else(|_| {
+            report_error!("Unknown surface material {value}");
+            Self::default()
+        })
+    }
+}
+
+fn tile_custom_data(
+    tile_map_layer: &Gd<TileMapLayer>,
+    collision_position: Vector2,
+    collision_normal: Vector2,
+    collision_mask: u32,
+) -> Option<Variant> {
+    let tile_set = tile_map_layer.get_tile_set()?;
+    if !tile_set.has_custom_data_layer_by_name(SURFACE_MATERIAL) {
+        return None;
+    }
+    // As with the collision polygons, a collision on the edge of a tile may
+    // map to an empty or different neighboring cell, so pick the cell whose
+    // polygon contains a point just inside the collider.
+    // The normal is global, so bring it into the layer's space, which may be
+    // rotated or scaled.
+    let local_normal = tile_map_layer
+        .get_global_transform()
+        .basis_xform_inv(collision_normal)
+        .normalized_or_zero();
+    let local_collision = tile_map_layer.to_local(collision_position);
+    let inside = local_collision - local_normal * STEP_INTO_COLLIDER;
+    let map_coordinates = tile_map_layer.local_to_map(inside);
+    let mut cells = vec![map_coordinates];
+    cells.extend(
+        tile_map_layer
+            .get_surrounding_cells(map_coordinates)
+            .iter_shared(),
+    );
+    let hit_cell = cells.iter().copied().find(|cell| {
+        tile_polygons(tile_map_layer, *cell, collision_mask)
+            .iter()
+            .any(|polygon| is_point_in_polygon(to_vec2(inside), &to_polygon(polygon)))
+    });
+    // Otherwise, fall back to the nearest tile.
+    hit_cell
+        .into_iter()
+        .chain(cells)
+        .find_map(|cell| tile_map_layer.get_cell_tile_data(cell))
+        .map(|tile_data| tile_data.get_custom_data(SURFACE_MATERIAL))
+}
@@ -1,4 +1,5 @@
 use crate::alligator::Alligator;
+use crate::report_error;
 use godot::classes::{CharacterBody2D, InputEvent, InputEventKey, InputEventScreenTouch, Node2D};
 use godot::global::Key;
 use godot::prelude::*;
@@ -74,7 +75,7 @@ impl TestAlligator {
         if let Some(mut parent) = player.get_parent() {
             parent.remove_child(&player);
         } else {
-            godot_error!("Attempting to eat player not in tree?");
+            report_error!("Attempting to eat player not in tree?");
         }
         player.queue_free();
     }
@@ -0,0 +1,143 @@
+use crate::player::Player;
+use godot::prelude::*;
+
+/// Expected outcome of a test scene, checked by the `TestRunner` after the
+/// scene has run for `duration_sec`.
+#[derive(GodotClass)]
+#[class(base=Node)]
+pub struct TestExpectations {
+    /// The `Player` whose state is checked.
+    #[export]
+    player_path: NodePath,
+    /// How long to let the scene run (in seconds) before checking.
+    #[export]
+    duration_sec: f32,
+    #[export]
+    check_position: bool,
+    /// Expected position of the player, local to its parent.
+    #[export]
+    expected_position: Vector2,
+    #[export]
+    position_tolerance: f32,
+    #[export]
+    check_rotation: bool,
+    #[export]
+    expected_rotation_degrees: f32,
+    #[export]
+    rotation_tolerance_degrees: f32,
+    #[export]
+    check_on_ceiling: bool,
+    #[export]
+    expected_on_ceiling: bool,
+    #[export]
+    check_region: bool,
+    /// Region the player should end up in, local to its parent. Useful when
+    /// the player rides something whose exact position varies.
+    #[export]
+    expected_region: Rect2,
+    #[export]
+    check_health: bool,
+    /// In health mode, how many hits the player should have left.
+    #[export]
+    expected_health: u32,
+    /// Expect the player to have died, so that the level removed it.
+    #[export]
+    expect_removed: bool,
+    base: Base<Node>,
+}
+
+#[godot_api]
+impl INode for TestExpectations {
+    fn init(base: Base<Node>) -> Self {
+        Self {
+            player_path: NodePath::from("../Player"),
+            duration_sec: 3.0,
+            check_position: false,
+            expected_position: Vector2::ZERO,
+            position_tolerance: 2.0,
+            check_rotation: false,
+            expected_rotation_degrees: 0.0,
+            rotation_tolerance_degrees: 1.0,
+            check_on_ceiling: false,
+            expected_on_ceiling: false,
+            check_region: false,
+            expected_region: Rect2::default(),
+            check_health: false,
+            expected_health: 0,
+            expect_removed: false,
+            base,
+        }
+    }
+
+    fn ready(&mut self) {
+        // Allows the `TestRunner` to find this node.
+        self.base_mut().add_to_group("test_expectations");
+    }
+}
+
+impl TestExpectations {
+    pub fn duration_sec(&self) -> f32 {
+        self.duration_sec
+    }
+
+    // Return a description of each check that failed.
+    pub fn check(&self) -> Vec<String> {
+        let player = self.base().try_get_node_as::<Player>(&self.player_path);
+        let player = match (player, self.expect_removed) {
+            (Some(player), false) => player,
+            (None, false) => return vec![format!("No player at {}", self.player_path)],
+            (Some(_), true) => return vec!["Expected the player to die".to_string()],
+            (None, true) => return Vec::new(),
+        };
+        let mut failures = Vec::new();
+        if self.check_position {
+            let position = player.get_position();
+            if position.distance_to(self.expected_position) > self.position_tolerance {
+                failures.push(format!(
+                    "Expected position {} (within {}), found {position}",
+                    self.expected_position, self.position_tolerance
+                ));
+            }
+        }
+        if self.check_region {
+            let position = player.get_position();
+            if !self.expected_region.contains_point(position) {
+                failures.push(format!(
+                    "Expected position in {}, found {position}",
+                    self.expected_region
+                ));
+            }
+        }
+        if self.check_rotation {
+            let rotation = player.get_rotation_degrees();
+            // Compare angles in the range [-180, 180).
+            let difference =
+                (rotation - self.expected_rotation_degrees + 180.0).rem_euclid(360.0) - 180.0;
+            if difference.abs() > self.rotation_tolerance_degrees {
+                failures.push(format!(
+                    "Expected rotation {} degrees (within {}), found {rotation}",
+                    self.expected_rotation_degrees, self.rotation_tolerance_degrees
+                ));
+            }
+        }
+        if self.check_on_ceiling {
+            let on_ceiling = player.bind().on_ceiling();
+            if on_ceiling != self.expected_on_ceiling {
+                failures.push(format!(
+                    "Expected on_ceiling to be {}, found {on_ceiling}",
+                    self.expected_on_ceiling
+                ));
+            }
+        }
+        if self.check_health {
+            let health = player.bind().health();
+            if health != self.expected_health {
+                failures.push(format!(
+                    "Expected health {}, found {health}",
+                    self.expected_health
+                ));
+            }
+        }
+        failures
+    }
+}
@@ -0,0 +1,164 @@
+use crate::log;
+use crate::scene_list::SceneList;
+use crate::test_expectations::TestExpectations;
+use godot::classes::Engine;
+use godot::prelude::*;
+
+/// Runs each scene in `scenes` without any input (other than a `JumpScript`)
+/// and reports any failures. Intended to be run headless, e.g.
+///
+/// `godot --headless --path godot res://test_scenes/test_runner.tscn`
+///
+/// Each scene fails if it reports an error or if its `TestExpectations` (if
+/// any) are not met. Only errors reported via `report_error!`, which this
+/// crate uses instead of `godot_error!`, are seen. Errors from Godot itself
+/// are not. Quits with a non-zero exit code if any scene fails.
+#[derive(GodotClass)]
+#[class(base=Node)]
+struct TestRunner {
+    /// Scenes to test, in order.
+    #[export]
+    scenes: Array<Gd<PackedScene>>,
+    /// If set, test its scenes instead of `scenes`.
+    #[export]
+    scene_list: Option<Gd<SceneList>>,
+    /// How long (in seconds) to run scenes that have no `TestExpectations`.
+    #[export]
+    default_duration_sec: f32,
+    /// Run the game this many times faster than real time. Physics still
+    /// steps at the usual rate, so behavior should match normal play.
+    #[export]
+    speed_up: i32,
+    scene_index: usize,
+    elapsed_sec: f64,
+    duration_sec: f64,
+    active_scene: Option<Gd<Node>>,
+    // Number of scenes that failed.
+    failures: usize,
+    base: Base<Node>,
+}
+
+#[godot_api]
+impl INode for TestRunner {
+    fn init(base: Base<Node>) -> Self {
+        Self {
+            scenes: Array::<Gd<PackedScene>>::new(),
+            scene_list: None,
+            default_duration_sec: 3.0,
+            speed_up: 1,
+            scene_index: 0,
+            elapsed_sec: 0.0,
+            duration_sec: 0.0,
+            active_scene: None,
+            failures: 0,
+            base,
+        }
+    }
+
+    fn ready(&mut self) {
+        if let Some(scene_list) = &self.scene_list {
+            self.scenes = scene_list.bind().scenes();
+        }
+        speed_up_engine(self.speed_up);
+        self.load_scene();
+    }
+
+    fn physics_process(&mut self, delta: f64) {
+        if self.active_scene.is_none() {
+            return;
+        }
+        self.elapsed_sec += delta;
+        if self.elapsed_sec < self.duration_sec {
+            return;
+        }
+        self.check_scene();
+        if let Some(mut scene) = self.active_scene.take() {
+            self.base_mut().remove_child(&scene);
+            scene.queue_free();
+        }
+        self.scene_index += 1;
+        self.load_scene();
+    }
+}
+
+// Run the game `speed_up` times faster than real time. Scaling time alone would
+// lengthen each physics step, so increase the tick rate by the same amount so
+// that each step is unchanged.
+pub fn speed_up_engine(speed_up: i32) {
+    let speed_up = speed_up.max(1);
+    let mut engine = Engine::singleton();
+    let ticks_per_second = engine.get_physics_ticks_per_second();
+    engine.set_physics_ticks_per_second(ticks_per_second * speed_up);
+    engine.set_max_physics_steps_per_frame(8 * speed_up);
+    engine.set_time_scale(speed_up as f64);
+}
+
+impl TestRunner {
+    fn load_scene(&mut self) {
+        let packed_scene = match self.scenes.get(self.scene_index) {
+            Some(packed_scene) => packed_scene,
+            None => {
+                self.finish();
+                return;
+            }
+        };
+        // Ignore errors from the prior scene as it was freed.
+        log::take_recorded_errors();
+
+        let node = packed_scene
+            .instantiate()
+            .expect("Failed to instantiate scene");
+        self.base_mut().add_child(&node);
+        self.duration_sec = match self.expectations() {
+            Some(expectations) => expectations.bind().duration_sec(),
+            None => self.default_duration_sec,
+        } as f64;
+        self.elapsed_sec = 0.0;
+        self.active_scene = Some(node);
+    }
+
+    fn expectations(&self) -> Option<Gd<TestExpectations>> {
+        self.base()
+            .get_tree()?
+            .get_nodes_in_group("test_expectations")
+            .iter_shared()
+            .find_map(|node| node.try_cast::<TestExpectations>().ok())
+    }
+
+    fn check_scene(&mut self) {
+        let mut scene_failures = match self.expectations() {
+            Some(expectations) => expectations.bind().check(),
+            None => Vec::new(),
+        };
+        for error in log::take_recorded_errors() {
+            scene_failures.push(format!("Reported error: {error}"));
+        }
+
+        let name = self
+            .active_scene
+            .as_ref()
+            .map_or(GString::new(), |scene| scene.get_scene_file_path());
+        if scene_failures.is_empty() {
+            godot_print!("PASS {name}");
+        } else {
+            godot_print!("FAIL {name}");
+            for failure in scene_failures {
+                godot_print!("\t{failure}");
+            }
+            self.failures += 1;
+        }
+    }
+
+    fn finish(&mut self) {
+        let exit_code = if self.failures == 0 {
+            godot_print!("All {} scenes passed", self.scenes.len());
+            0
+        } else {
+            godot_print!("{} of {} scenes failed", self.failures, self.scenes.len());
+            1
+        };
+        if let Some(mut scene_tree) = self.base().get_tree() {
+            scene_tree.quit_ex().exit_code(exit_code).done();
+        }
+    }
+}
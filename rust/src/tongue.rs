@@ -0,0 +1,363 @@
+use godot::classes::{IRayCast2D, Input, RayCast2D, TileMapLayer};
+use godot::prelude::*;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+// Groups of nodes that the tongue can catch. Each must have an `eat` method.
+pub const PREY_GROUPS: [&str; 2] = ["prey", "bonus_prey"];
+// Nodes in this group, such as a `Marker2D` at the end of a branch, can be
+// grappled.
+const GRAPPLE_POINT_GROUP: &str = "grapple_points";
+// Name of the `TileSet` custom data layer (a bool) marking tiles that can be
+// grappled.
+const GRAPPLE_TILE: &str = "grapple";
+// How far from a grapple point the tongue may hit the world and still latch
+// on, e.g. at the edge of a grapple tile.
+const GRAPPLE_TOLERANCE: f32 = 48.0;
+
+pub trait TongueDetector {
+    // Whether the tongue button was pressed since the last physics frame.
+    fn is_tongue_just_pressed(&mut self) -> bool;
+}
+
+struct TongueKeyDetector;
+
+impl TongueDetector for TongueKeyDetector {
+    fn is_tongue_just_pressed(&mut self) -> bool {
+        Input::singleton().is_action_just_pressed("tongue")
+    }
+}
+
+/// `TongueDetector` that allows specifying whether the tongue button is
+/// pressed in a script.
+pub struct ScriptedTongueDetector {
+    pressed: Arc<AtomicBool>,
+    was_pressed: bool,
+}
+
+impl ScriptedTongueDetector {
+    pub fn new(atomic_bool: &Arc<AtomicBool>) -> Self {
+        ScriptedTongueDetector {
+            pressed: atomic_bool.clone(),
+            was_pressed: false,
+        }
+    }
+}
+
+impl TongueDetector for ScriptedTongueDetector {
+    fn is_tongue_just_pressed(&mut self) -> bool {
+        let pressed = self.pressed.load(Ordering::SeqCst);
+        let just_pressed = pressed && !self.was_pressed;
+        self.was_pressed = pressed;
+        just_pressed
+    }
+}
+
+enum State {
+    Ready,
+    // Reaching `reach` pixels towards `target`, if any. Otherwise, the tongue
+    // missed and reaches its full range.
+    Extending {
+        target: Option<Gd<Node2D>>,
+        length: f32,
+        reach: f32,
+    },
+    Retracting {
+        length: f32,
+    },
+    // Latched onto a grapple point at the global `anchor`.
+    Grappling {
+        anchor: Vector2,
+        length: f32,
+    },
+}
+
+/// The player's tongue, which catches flies at range. Casts a ray to the
+/// nearest fly in front of the player, to check that nothing is in the way.
+/// In mid-air, it can latch onto grapple points instead: nodes in the
+/// "grapple_points" group, or tiles with the "grapple" custom data.
+#[derive(GodotClass)]
+#[class(base=RayCast2D)]
+pub struct Tongue {
+    /// How far the tongue reaches, in pixels.
+    #[export]
+    range: f32,
+    /// How fast the tongue extends and retracts, in pixels per second.
+    #[export]
+    speed: f32,
+    /// How long after retracting before the tongue can be used again.
+    #[export]
+    cooldown_sec: f32,
+    #[export]
+    color: Color,
+    #[export]
+    width: f32,
+    state: State,
+    // The global direction the tongue points in.
+    aim: Vector2,
+    cooldown_remaining_sec: f32,
+    tongue_detector: Box<dyn TongueDetector>,
+    base: Base<RayCast2D>,
+}
+
+#[godot_api]
+impl IRayCast2D for Tongue {
+    fn init(base: Base<RayCast2D>) -> Self {
+        Self {
+            range: 160.0,
+            speed: 1200.0,
+            cooldown_sec: 0.5,
+            color: Color::from_rgb(0.9, 0.35, 0.45),
+            width: 4.0,
+            state: State::Ready,
+            aim: Vector2::RIGHT,
+            cooldown_remaining_sec: 0.0,
+            tongue_detector: Box::new(TongueKeyDetector),
+            base,
+        }
+    }
+
+    fn physics_process(&mut self, delta: f64) {
+        let distance = self.speed * delta as f32;
+        let origin = self.base().get_global_position();
+        match &mut self.state {
+            State::Ready => {
+                self.cooldown_remaining_sec = (self.cooldown_remaining_sec - delta as f32).max(0.0);
+                return;
+            }
+            State::Extending {
+                target,
+                length,
+                reach,
+            } => {
+                *length += distance;
+                // Follow the target as it flies.
+                let target = target.clone().filter(|target| target.is_instance_valid());
+                if let Some(target) = &target {
+                    let offset = target.get_global_position() - origin;
+                    if let Some(aim) = offset.try_normalized() {
+                        self.aim = aim;
+                    }
+                    *reach = offset.length();
+                }
+                if *length >= *reach {
+                    let reach = *reach;
+                    if let Some(mut target) = target {
+                        target.call("eat", &[]);
+                    }
+                    self.state = State::Retracting { length: reach };
+                }
+            }
+            State::Grappling { anchor, length } => {
+                *length += distance;
+                if let Some(aim) = (*anchor - origin).try_normalized() {
+                    self.aim = aim;
+                }
+            }
+            State::Retracting { length } => {
+                *length -= distance;
+                if *length <= 0.0 {
+                    self.state = State::Ready;
+                    self.cooldown_remaining_sec = self.cooldown_sec;
+                }
+            }
+        }
+        self.base_mut().queue_redraw();
+    }
+
+    fn draw(&mut self) {
+        let length = match self.state {
+            State::Ready => return,
+            State::Extending { length, reach, .. } => length.min(reach),
+            State::Retracting { length } => length,
+            State::Grappling { anchor, length } => {
+                length.min(self.base().get_global_position().distance_to(anchor))
+            }
+        };
+        // The player rotates to land on walls and ceilings, so draw in global
+        // coordinates, as `JumpMeter` does.
+        let inverse_transform = self.base().get_global_transform().affine_inverse();
+        let start = self.base().get_global_position();
+        let end = start + self.aim * length;
+        let (color, width) = (self.color, self.width);
+        self.base_mut().draw_set_transform_matrix(inverse_transform);
+        self.base_mut()
+            .draw_line_ex(start, end, color)
+            .width(width)
+            .done();
+    }
+}
+
+#[godot_api]
+impl Tongue {
+    // Shoot the tongue at the nearest fly within range in the global `facing`
+    // direction, or straight ahead if there is none. Returns `false` if the
+    // tongue is still in use or cooling down.
+    pub fn lick(&mut self, facing: Vector2) -> bool {
+        if !matches!(self.state, State::Ready) || self.cooldown_remaining_sec > 0.0 {
+            return false;
+        }
+        let target = self.nearest_prey(facing);
+        let reach = match &target {
+            Some(target) => {
+                let offset = target.get_global_position() - self.base().get_global_position();
+                self.aim = offset.try_normalized().unwrap_or(facing);
+                offset.length()
+            }
+            None => {
+                self.aim = facing;
+                self.range
+            }
+        };
+        self.state = State::Extending {
+            target,
+            length: 0.0,
+            reach,
+        };
+        true
+    }
+
+    // Latch onto the nearest grapple point within range that is above the
+    // player, where `up` is opposite gravity. Returns the global point where
+    // the tongue is anchored.
+    pub fn grapple(&mut self, up: Vector2) -> Option<Vector2> {
+        if !matches!(self.state, State::Ready) || self.cooldown_remaining_sec > 0.0 {
+            return None;
+        }
+        let origin = self.base().get_global_position();
+        let mut points = self.grapple_points();
+        points.retain(|point| {
+            let offset = *point - origin;
+            offset.length() <= self.range && offset.dot(up) > 0.0
+        });
+        points.sort_by(|a, b| {
+            origin
+                .distance_squared_to(*a)
+                .total_cmp(&origin.distance_squared_to(*b))
+        });
+        let anchor = points
+            .into_iter()
+            .find_map(|point| self.grapple_anchor(point))?;
+        self.aim = (anchor - origin).try_normalized().unwrap_or(up);
+        self.state = State::Grappling {
+            anchor,
+            length: 0.0,
+        };
+        Some(anchor)
+    }
+
+    // Let go of the grapple point, if holding one.
+    pub fn release(&mut self) {
+        if let State::Grappling { anchor, .. } = self.state {
+            let length = self.base().get_global_position().distance_to(anchor);
+            self.state = State::Retracting { length };
+        }
+    }
+
+    pub fn is_tongue_just_pressed(&mut self) -> bool {
+        self.tongue_detector.is_tongue_just_pressed()
+    }
+
+    pub fn replace_tongue_detector(&mut self, detector: Box<dyn TongueDetector>) {
+        self.tongue_detector = detector;
+    }
+
+    fn grapple_points(&self) -> Vec<Vector2> {
+        let mut points: Vec<Vector2> = self
+            .base()
+            .get_tree()
+            .map(|mut scene_tree| {
+                scene_tree
+                    .get_nodes_in_group(GRAPPLE_POINT_GROUP)
+                    .iter_shared()
+                    .filter_map(|node| node.try_cast::<Node2D>().ok())
+                    .map(|node| node.get_global_position())
+                    .collect()
+            })
+            .unwrap_or_default();
+        // The tongue belongs to the `Player`, whose parent is the `Level`.
+        let level = self
+            .base()
+            .get_parent()
+            .and_then(|player| player.get_parent())
+            .and_then(|level| level.try_cast::<TileMapLayer>().ok());
+        if let Some(level) = level {
+            points.extend(grapple_tiles(&level));
+        }
+        points
+    }
+
+    // Where the tongue latches on when aimed at the grapple `point`, if it
+    // can reach.
+    fn grapple_anchor(&mut self, point: Vector2) -> Option<Vector2> {
+        let target_position = self.base().to_local(point);
+        self.base_mut().set_target_position(target_position);
+        self.base_mut().force_raycast_update();
+        if !self.base().is_colliding() {
+            return Some(point);
+        }
+        let collision_point = self.base().get_collision_point();
+        (collision_point.distance_to(point) <= GRAPPLE_TOLERANCE).then_some(collision_point)
+    }
+
+    fn nearest_prey(&mut self, facing: Vector2) -> Option<Gd<Node2D>> {
+        let mut scene_tree = self.base().get_tree()?;
+        let origin = self.base().get_global_position();
+        let mut nearest: Option<(f32, Gd<Node2D>)> = None;
+        for group in PREY_GROUPS {
+            for node in scene_tree.get_nodes_in_group(group).iter_shared() {
+                let Ok(prey) = node.try_cast::<Node2D>() else {
+                    continue;
+                };
+                let offset = prey.get_global_position() - origin;
+                let distance = offset.length();
+                if distance > self.range || offset.dot(facing) <= 0.0 {
+                    continue;
+                }
+                if nearest
+                    .as_ref()
+                    .is_some_and(|(nearest_distance, _)| *nearest_distance <= distance)
+                {
+                    continue;
+                }
+                if self.can_reach(&prey) {
+                    nearest = Some((distance, prey));
+                }
+            }
+        }
+        nearest.map(|(_, prey)| prey)
+    }
+
+    // Whether the ray hits `prey` before anything else, such as a wall.
+    fn can_reach(&mut self, prey: &Gd<Node2D>) -> bool {
+        let target_position = self.base().to_local(prey.get_global_position());
+        self.base_mut().set_target_position(target_position);
+        self.base_mut().force_raycast_update();
+        self.base().is_colliding()
+            && self
+                .base()
+                .get_collider()
+                .is_some_and(|collider| collider.instance_id() == prey.instance_id())
+    }
+}
+
+// The global centers of the tiles in `level` marked as grapple points.
+fn grapple_tiles(level: &Gd<TileMapLayer>) -> Vec<Vector2> {
+    let has_grapple_tiles = level
+        .get_tile_set()
+        .is_some_and(|tile_set| tile_set.has_custom_data_layer_by_name(GRAPPLE_TILE));
+    if !has_grapple_tiles {
+        return Vec::new();
+    }
+    level
+        .get_used_cells()
+        .iter_shared()
+        .filter(|cell| {
+            level
+                .get_cell_tile_data(*cell)
+                .is_some_and(|tile_data| tile_data.get_custom_data(GRAPPLE_TILE).booleanize())
+        })
+        .map(|cell| level.to_global(level.map_to_local(cell)))
+        .collect()
+}
@@ -0,0 +1,70 @@
+use crate::report_error;
+use crate::tongue::{ScriptedTongueDetector, Tongue, TongueDetector};
+use godot::prelude::*;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Presses the tongue button according to a script, so that a scene can lick
+/// or grapple without any input, as `JumpScript` does for jumps. Used by test
+/// scenes.
+#[derive(GodotClass)]
+#[class(base=Node)]
+pub struct TongueScript {
+    /// The `Tongue` to drive.
+    #[export]
+    tongue_path: NodePath,
+    /// How long to wait in ms (after the scene starts or the previous press)
+    /// before each press. Each press lasts one physics frame.
+    #[export]
+    presses: PackedFloat32Array,
+    // Whether the script is pretending that the tongue button is pressed.
+    pressed: Arc<AtomicBool>,
+    next_press: usize,
+    curr_time_ms: f64,
+    next_step_time_ms: f64,
+    base: Base<Node>,
+}
+
+#[godot_api]
+impl INode for TongueScript {
+    fn init(base: Base<Node>) -> Self {
+        Self {
+            tongue_path: NodePath::from("../Player/Tongue"),
+            presses: PackedFloat32Array::new(),
+            pressed: Arc::new(AtomicBool::new(false)),
+            next_press: 0,
+            curr_time_ms: 0.0,
+            next_step_time_ms: 0.0,
+            base,
+        }
+    }
+
+    fn ready(&mut self) {
+        match self.base().try_get_node_as::<Tongue>(&self.tongue_path) {
+            Some(mut tongue) => {
+                let detector: Box<dyn TongueDetector> =
+                    Box::new(ScriptedTongueDetector::new(&self.pressed));
+                tongue.bind_mut().replace_tongue_detector(detector);
+            }
+            None => report_error!("TongueScript has no Tongue at {}", self.tongue_path),
+        }
+        if let Some(press) = self.presses.get(0) {
+            self.next_step_time_ms = press as f64;
+        }
+    }
+
+    fn physics_process(&mut self, delta: f64) {
+        if self.pressed.load(Ordering::SeqCst) {
+            self.pressed.store(false, Ordering::SeqCst);
+            self.next_press += 1;
+            if let Some(next_press) = self.presses.get(self.next_press) {
+                self.next_step_time_ms += next_press as f64;
+            }
+        }
+        self.curr_time_ms += delta * 1000.0;
+        if self.curr_time_ms >= self.next_step_time_ms && self.next_press < self.presses.len() {
+            self.pressed.store(true, Ordering::SeqCst);
+        }
+    }
+}
@@ -2,32 +2,12 @@ use godot::classes::{InputEvent, Label};
 use godot::prelude::*;
 
 use crate::button_hint::ButtonHint;
-use crate::jump_handler::{JumpDetector, JumpHandler};
+use crate::jump_handler::{JumpDetector, JumpHandler, ScriptedJumpDetector};
 use crate::player::{Player, PlayerInfo};
 
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
-/// `JumpDetector` that allows specifying whether the jump button is pressed in
-/// a script.
-struct TutorialJumpDetector {
-    pressed: Arc<AtomicBool>,
-}
-
-impl TutorialJumpDetector {
-    fn new(atomic_bool: &Arc<AtomicBool>) -> Self {
-        TutorialJumpDetector {
-            pressed: atomic_bool.clone(),
-        }
-    }
-}
-
-impl JumpDetector for TutorialJumpDetector {
-    fn is_jump_pressed(&mut self) -> bool {
-        self.pressed.load(Ordering::SeqCst)
-    }
-}
-
 /// Stages of the tutorial, using numbers to help keep them straight. Each step
 /// has a corresponding member variable that specifies how long after the
 /// previous step it should start.
@@ -97,7 +77,7 @@ impl INode2D for Tutorial {
     fn ready(&mut self) {
         self.player_start_info = Some(self.player().bind().get_player_info());
         self.next_step_time_ms = self.one_start_jump_ms as f64;
-        let detector: Box<dyn JumpDetector> = Box::new(TutorialJumpDetector::new(&self.pressed));
+        let detector: Box<dyn JumpDetector> = Box::new(ScriptedJumpDetector::new(&self.pressed));
         let mut jump_handler = self.base().get_node_as::<JumpHandler>("Player/JumpHandler");
         jump_handler.bind_mut().replace_jump_detector(detector);
     }
@@ -0,0 +1,109 @@
+use crate::player::Player;
+use godot::classes::{Area2D, Engine, FastNoiseLite, IArea2D};
+use godot::global::randi;
+use godot::prelude::*;
+use std::f32::consts::TAU;
+
+/// How the strength of a `WindZone` changes over time.
+#[derive(PartialEq, GodotConvert, Var, Export, Clone, Copy, Debug)]
+#[godot(via=GString)]
+pub enum Gust {
+    /// Always full strength.
+    Constant,
+    /// Smoothly rises to full strength and dies down again, once per period.
+    Pulse,
+    /// Varies randomly, changing roughly once per period.
+    Noise,
+}
+
+impl Default for Gust {
+    fn default() -> Self {
+        Self::Constant
+    }
+}
+
+/// Pushes the player while they are airborne inside it, bending their jumps.
+#[derive(GodotClass)]
+#[class(base=Area2D)]
+pub struct WindZone {
+    /// Acceleration (in pixels/second^2) at full strength, applied along with
+    /// the player's `fall_acceleration`.
+    #[export]
+    force: Vector2,
+    #[export]
+    gust: Gust,
+    /// In seconds. Ignored for `Constant` gusts.
+    #[export]
+    gust_period: f32,
+    noise: Gd<FastNoiseLite>,
+    elapsed_sec: f32,
+    base: Base<Area2D>,
+}
+
+#[godot_api]
+impl IArea2D for WindZone {
+    fn init(base: Base<Area2D>) -> Self {
+        Self {
+            force: Vector2::new(100.0, 0.0),
+            gust: Gust::Constant,
+            gust_period: 2.0,
+            noise: FastNoiseLite::new_gd(),
+            elapsed_sec: 0.0,
+            base,
+        }
+    }
+
+    fn ready(&mut self) {
+        if Engine::singleton().is_editor_hint() {
+            return;
+        }
+        // Avoid zones gusting in unison.
+        self.noise.set_seed(randi() as i32);
+
+        let gd = Gd::from_instance_id(self.base().instance_id());
+        self.base_mut()
+            .signals()
+            .body_entered()
+            .connect_obj(&gd, Self::on_body_entered);
+        self.base_mut()
+            .signals()
+            .body_exited()
+            .connect_obj(&gd, Self::on_body_exited);
+    }
+
+    fn physics_process(&mut self, delta: f64) {
+        self.elapsed_sec += delta as f32;
+    }
+}
+
+#[godot_api]
+impl WindZone {
+    // The force at this moment.
+    pub fn current_force(&self) -> Vector2 {
+        let period = self.gust_period.max(f32::EPSILON);
+        let strength = match self.gust {
+            Gust::Constant => 1.0,
+            Gust::Pulse => (1.0 - (self.elapsed_sec / period * TAU).cos()) / 2.0,
+            // `get_noise_1d` is roughly in [-1, 1].
+            Gust::Noise => {
+                let noise = self.noise.get_noise_1d(self.elapsed_sec / period);
+                ((noise + 1.0) / 2.0).clamp(0.0, 1.0)
+            }
+        };
+        self.force * strength
+    }
+
+    #[func]
+    fn on_body_entered(&mut self, body: Gd<Node2D>) {
+        if let Ok(mut player) = body.try_cast::<Player>() {
+            player.bind_mut().enter_wind_zone(self.to_gd());
+        }
+    }
+
+    #[func]
+    fn on_body_exited(&mut self, body: Gd<Node2D>) {
+        if let Ok(mut player) = body.try_cast::<Player>() {
+            player.bind_mut().exit_wind_zone(&self.to_gd());
+        }
+    }
+}